@@ -0,0 +1,329 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use huly_cef::{AudioPacket, AUDIO_FORMAT_VERSION, AUDIO_HEADER_SIZE, AUDIO_MAGIC};
+
+/// Sample format of binary audio messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AudioFormat {
+    F32 = 0,
+    /// Halves the bandwidth of [`AudioFormat::F32`].
+    S16 = 1,
+    /// 20 ms Opus packets. Streams that Opus can't encode, e.g. 44.1 kHz or surround,
+    /// are sent as [`AudioFormat::S16`].
+    #[cfg(feature = "opus")]
+    Opus = 2,
+}
+
+impl FromStr for AudioFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "f32" => Ok(AudioFormat::F32),
+            "s16" => Ok(AudioFormat::S16),
+            #[cfg(feature = "opus")]
+            "opus" => Ok(AudioFormat::Opus),
+            #[cfg(not(feature = "opus"))]
+            "opus" => Err(anyhow::anyhow!("built without the opus feature")),
+            _ => Err(anyhow::anyhow!("unknown audio format: {}", s)),
+        }
+    }
+}
+
+/// Encodes the audio packets of a tab socket.
+pub struct AudioEncoder {
+    format: AudioFormat,
+    #[cfg(feature = "opus")]
+    opus: Option<opus_stream::OpusStream>,
+}
+
+impl AudioEncoder {
+    pub fn new(format: AudioFormat) -> Self {
+        Self {
+            format,
+            #[cfg(feature = "opus")]
+            opus: None,
+        }
+    }
+
+    /// Drops the samples buffered for the previous stream.
+    pub fn reset(&mut self) {
+        #[cfg(feature = "opus")]
+        {
+            self.opus = None;
+        }
+    }
+
+    /// Writes the packet as a binary message. Returns false when there is nothing to
+    /// send yet, because the samples are buffered until a whole Opus packet is ready.
+    pub fn encode(&mut self, packet: &AudioPacket, buffer: &mut Vec<u8>) -> bool {
+        #[cfg(feature = "opus")]
+        if self.format == AudioFormat::Opus {
+            let stream = self
+                .opus
+                .get_or_insert_with(|| opus_stream::OpusStream::new(packet));
+            return stream.encode(packet, buffer);
+        }
+
+        encode_audio(packet, self.format, buffer);
+        true
+    }
+}
+
+fn write_header(
+    format: AudioFormat,
+    channels: u8,
+    sample_rate: u32,
+    frames: u32,
+    pts: i64,
+    buffer: &mut Vec<u8>,
+) {
+    buffer.clear();
+    buffer.extend_from_slice(&AUDIO_MAGIC);
+    buffer.push(AUDIO_FORMAT_VERSION);
+    buffer.push(format as u8);
+    buffer.push(channels);
+    buffer.push(0);
+    buffer.extend_from_slice(&sample_rate.to_le_bytes());
+    buffer.extend_from_slice(&frames.to_le_bytes());
+    buffer.extend_from_slice(&pts.to_le_bytes());
+}
+
+/// Writes an audio packet as a binary message, see [`AUDIO_FORMAT_VERSION`] for the layout.
+pub fn encode_audio(packet: &AudioPacket, format: AudioFormat, buffer: &mut Vec<u8>) {
+    write_header(
+        format,
+        packet.channels,
+        packet.sample_rate,
+        packet.frames,
+        packet.pts,
+        buffer,
+    );
+
+    match format {
+        AudioFormat::F32 => {
+            buffer.reserve(packet.data.len() * 4);
+            for sample in packet.data.iter() {
+                buffer.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        AudioFormat::S16 => {
+            buffer.reserve(packet.data.len() * 2);
+            for sample in packet.data.iter() {
+                let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                buffer.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => unreachable!("Opus packets are written by AudioEncoder"),
+    }
+}
+
+#[cfg(feature = "opus")]
+mod opus_stream {
+    use huly_cef::AudioPacket;
+    use log::warn;
+    use opus::{Application, Channels, Encoder};
+
+    use super::{encode_audio, write_header, AudioFormat};
+
+    /// Opus packets are 20 ms long.
+    const PACKETS_PER_SECOND: u32 = 50;
+    /// Recommended maximum size of an Opus packet.
+    const MAX_PACKET_SIZE: usize = 4000;
+
+    /// Buffers the samples of a stream until they fill whole Opus packets.
+    pub struct OpusStream {
+        /// `None` if Opus can't encode the stream, it is then sent as s16.
+        encoder: Option<Encoder>,
+        channels: u8,
+        sample_rate: u32,
+        pending: Vec<f32>,
+        /// Timestamp of the first pending sample.
+        pts: i64,
+        packet: Vec<u8>,
+    }
+
+    impl OpusStream {
+        pub fn new(packet: &AudioPacket) -> Self {
+            let channels = match packet.channels {
+                1 => Some(Channels::Mono),
+                2 => Some(Channels::Stereo),
+                _ => None,
+            };
+            let encoder = channels
+                .ok_or_else(|| format!("{} channels", packet.channels))
+                .and_then(|channels| {
+                    Encoder::new(packet.sample_rate, channels, Application::Audio)
+                        .map_err(|e| format!("{} Hz: {}", packet.sample_rate, e))
+                });
+            let encoder = match encoder {
+                Ok(encoder) => Some(encoder),
+                Err(e) => {
+                    warn!("can't encode the audio stream as Opus, sending s16: {}", e);
+                    None
+                }
+            };
+
+            Self {
+                encoder,
+                channels: packet.channels,
+                sample_rate: packet.sample_rate,
+                pending: Vec::new(),
+                pts: packet.pts,
+                packet: vec![0; MAX_PACKET_SIZE],
+            }
+        }
+
+        pub fn encode(&mut self, packet: &AudioPacket, buffer: &mut Vec<u8>) -> bool {
+            let Some(encoder) = self.encoder.as_mut() else {
+                encode_audio(packet, AudioFormat::S16, buffer);
+                return true;
+            };
+
+            if self.pending.is_empty() {
+                self.pts = packet.pts;
+            }
+            self.pending.extend_from_slice(&packet.data);
+
+            let frames = (self.sample_rate / PACKETS_PER_SECOND) as usize;
+            let packet_samples = frames * self.channels as usize;
+            let packets = self.pending.len() / packet_samples;
+            if packets == 0 {
+                return false;
+            }
+
+            write_header(
+                AudioFormat::Opus,
+                self.channels,
+                self.sample_rate,
+                (packets * frames) as u32,
+                self.pts,
+                buffer,
+            );
+            for samples in self.pending.chunks_exact(packet_samples).take(packets) {
+                match encoder.encode_float(samples, &mut self.packet) {
+                    Ok(size) => {
+                        buffer.extend_from_slice(&(size as u16).to_le_bytes());
+                        buffer.extend_from_slice(&self.packet[..size]);
+                    }
+                    // Decoded as a lost packet.
+                    Err(e) => {
+                        warn!("failed to encode an Opus packet: {}", e);
+                        buffer.extend_from_slice(&0u16.to_le_bytes());
+                    }
+                }
+            }
+
+            self.pending.drain(..packets * packet_samples);
+            self.pts += (packets * 1000 / PACKETS_PER_SECOND as usize) as i64;
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn packet() -> AudioPacket {
+        AudioPacket {
+            data: Arc::new(vec![0.5, -0.25, 2.0, -2.0]),
+            frames: 2,
+            channels: 2,
+            sample_rate: 48000,
+            pts: 1234,
+        }
+    }
+
+    #[test]
+    fn format_from_str() {
+        assert_eq!("f32".parse::<AudioFormat>().unwrap(), AudioFormat::F32);
+        assert_eq!("S16".parse::<AudioFormat>().unwrap(), AudioFormat::S16);
+        assert!("mp3".parse::<AudioFormat>().is_err());
+        #[cfg(feature = "opus")]
+        assert_eq!("opus".parse::<AudioFormat>().unwrap(), AudioFormat::Opus);
+        #[cfg(not(feature = "opus"))]
+        assert!("opus".parse::<AudioFormat>().is_err());
+    }
+
+    #[test]
+    fn header_layout() {
+        let mut buffer = Vec::new();
+        encode_audio(&packet(), AudioFormat::F32, &mut buffer);
+
+        assert_eq!(buffer.len(), AUDIO_HEADER_SIZE + 4 * 4);
+        assert_eq!(buffer[0..4], AUDIO_MAGIC);
+        assert_eq!(buffer[4], AUDIO_FORMAT_VERSION);
+        assert_eq!(buffer[5], AudioFormat::F32 as u8);
+        assert_eq!(buffer[6], 2);
+        assert_eq!(buffer[8..12], 48000u32.to_le_bytes());
+        assert_eq!(buffer[12..16], 2u32.to_le_bytes());
+        assert_eq!(buffer[16..24], 1234i64.to_le_bytes());
+        assert_eq!(buffer[24..28], 0.5f32.to_le_bytes());
+        assert_eq!(buffer[36..40], (-2.0f32).to_le_bytes());
+    }
+
+    #[test]
+    fn s16_samples_are_clamped() {
+        let mut buffer = Vec::new();
+        encode_audio(&packet(), AudioFormat::S16, &mut buffer);
+
+        let samples: Vec<i16> = buffer[AUDIO_HEADER_SIZE..]
+            .chunks_exact(2)
+            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+            .collect();
+        assert_eq!(samples, [16383, -8191, i16::MAX, -i16::MAX]);
+    }
+
+    #[cfg(feature = "opus")]
+    fn silence(frames: u32, sample_rate: u32, pts: i64) -> AudioPacket {
+        AudioPacket {
+            data: Arc::new(vec![0.0; frames as usize * 2]),
+            frames,
+            channels: 2,
+            sample_rate,
+            pts,
+        }
+    }
+
+    #[cfg(feature = "opus")]
+    #[test]
+    fn opus_packets_are_buffered() {
+        let mut encoder = AudioEncoder::new(AudioFormat::Opus);
+        let mut buffer = Vec::new();
+
+        // 10 ms, half an Opus packet.
+        assert!(!encoder.encode(&silence(480, 48000, 100), &mut buffer));
+        // The first packet is complete.
+        assert!(encoder.encode(&silence(480, 48000, 110), &mut buffer));
+        assert_eq!(buffer[5], AudioFormat::Opus as u8);
+        assert_eq!(buffer[12..16], 960u32.to_le_bytes());
+        assert_eq!(buffer[16..24], 100i64.to_le_bytes());
+        let size = u16::from_le_bytes([buffer[24], buffer[25]]) as usize;
+        assert_eq!(buffer.len(), AUDIO_HEADER_SIZE + 2 + size);
+
+        // 40 ms, two packets.
+        assert!(encoder.encode(&silence(1920, 48000, 120), &mut buffer));
+        assert_eq!(buffer[12..16], 1920u32.to_le_bytes());
+        assert_eq!(buffer[16..24], 120i64.to_le_bytes());
+
+        encoder.reset();
+        assert!(!encoder.encode(&silence(480, 48000, 500), &mut buffer));
+    }
+
+    #[cfg(feature = "opus")]
+    #[test]
+    fn unsupported_opus_streams_are_sent_as_s16() {
+        let mut encoder = AudioEncoder::new(AudioFormat::Opus);
+        let mut buffer = Vec::new();
+
+        assert!(encoder.encode(&silence(441, 44100, 0), &mut buffer));
+        assert_eq!(buffer[5], AudioFormat::S16 as u8);
+        assert_eq!(buffer.len(), AUDIO_HEADER_SIZE + 441 * 2 * 2);
+    }
+}
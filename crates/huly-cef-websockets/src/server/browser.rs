@@ -1,5 +1,10 @@
+use std::{path::Path, time::Duration};
+
 use futures::{SinkExt, StreamExt};
-use huly_cef::{browser::Browser, MouseButton};
+use huly_cef::{
+    browser::Browser, ClipboardData, DropData, EmulationSettings, MouseButton, PdfOptions,
+    ScreenshotOptions, TextRange, TouchPoint, MAX_FRAME_RATE,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 
@@ -54,6 +59,18 @@ pub async fn handle(state: SharedServerState, mut websocket: WebSocketStream<Tcp
                 Ok(params) => screenshot(&state, params).await,
                 Err(err) => Err(err),
             },
+            "printToPdf" => match parse_params(request.params) {
+                Ok(params) => print_to_pdf(&state, params).await,
+                Err(err) => Err(err),
+            },
+            "setEmulation" => match parse_params(request.params) {
+                Ok(params) => set_emulation(&state, params).await,
+                Err(err) => Err(err),
+            },
+            "clearEmulation" => match parse_params(request.params) {
+                Ok(params) => clear_emulation(&state, params).await,
+                Err(err) => Err(err),
+            },
             "navigate" => match parse_params(request.params) {
                 Ok(params) => navigate(&state, params).await,
                 Err(err) => Err(err),
@@ -66,6 +83,10 @@ pub async fn handle(state: SharedServerState, mut websocket: WebSocketStream<Tcp
                 Ok(params) => get_clickable_elements(&state, params).await,
                 Err(err) => Err(err),
             },
+            "typeText" => match parse_params(request.params) {
+                Ok(params) => type_text(&state, params).await,
+                Err(err) => Err(err),
+            },
             "clickElement" => match parse_params(request.params) {
                 Ok(params) => click_element(&state, params).await,
                 Err(err) => Err(err),
@@ -118,14 +139,44 @@ fn handle_sync_method(
         "getSize" => parse_params(params).and_then(|_: EmptyParams| size(&state)),
         "getTitle" => parse_params(params).and_then(|params| title(&state, params)),
         "getUrl" => parse_params(params).and_then(|params| url(&state, params)),
+        "getZoomLevel" => parse_params(params).and_then(|params| zoom_level(&state, params)),
+        "setZoomLevel" => parse_params(params).and_then(|params| set_zoom_level(&state, params)),
+        "zoomIn" => parse_params(params).and_then(|params| zoom_in(&state, params)),
+        "zoomOut" => parse_params(params).and_then(|params| zoom_out(&state, params)),
+        "resetZoom" => parse_params(params).and_then(|params| reset_zoom(&state, params)),
+        "getEmulation" => parse_params(params).and_then(|params| get_emulation(&state, params)),
+        "getSubscriberStats" => {
+            parse_params(params).and_then(|params| subscriber_stats(&state, params))
+        }
         "resize" => parse_params(params).and_then(|params| resize(&state, params)),
+        "resizeTab" => parse_params(params).and_then(|params| resize_tab(&state, params)),
         "mouseMove" => parse_params(params).and_then(|params| mouse_move(&state, params)),
+        "touch" => parse_params(params).and_then(|params| touch(&state, params)),
+        "dragEnter" => parse_params(params).and_then(|params| drag_enter(&state, params)),
+        "dragOver" => parse_params(params).and_then(|params| drag_over(&state, params)),
+        "dragLeave" => parse_params(params).and_then(|params| drag_leave(&state, params)),
+        "drop" => parse_params(params).and_then(|params| drop_data(&state, params)),
         "click" => parse_params(params).and_then(|params| click(&state, params)),
         "wheel" => parse_params(params).and_then(|params| wheel(&state, params)),
         "key" => parse_params(params).and_then(|params| key(&state, params)),
         "char" => parse_params(params).and_then(|params| char(&state, params)),
+        "press" => parse_params(params).and_then(|params| press(&state, params)),
+        "imeSetComposition" => {
+            parse_params(params).and_then(|params| ime_set_composition(&state, params))
+        }
+        "imeCommitText" => parse_params(params).and_then(|params| ime_commit_text(&state, params)),
+        "imeFinishComposingText" => {
+            parse_params(params).and_then(|params| ime_finish_composing_text(&state, params))
+        }
+        "imeCancelComposition" => {
+            parse_params(params).and_then(|params| ime_cancel_composition(&state, params))
+        }
         "stopVideo" => parse_params(params).and_then(|params| stop_video(&state, params)),
         "startVideo" => parse_params(params).and_then(|params| start_video(&state, params)),
+        "setFrameRate" => parse_params(params).and_then(|params| set_frame_rate(&state, params)),
+        "setDevicePixelRatio" => {
+            parse_params(params).and_then(|params| set_device_pixel_ratio(&state, params))
+        }
         "setFocus" => parse_params(params).and_then(|params| set_focus(&state, params)),
         "undo" => parse_params(params).and_then(|params| undo(&state, params)),
         "redo" => parse_params(params).and_then(|params| redo(&state, params)),
@@ -133,6 +184,13 @@ fn handle_sync_method(
         "copy" => parse_params(params).and_then(|params| copy(&state, params)),
         "paste" => parse_params(params).and_then(|params| paste(&state, params)),
         "cut" => parse_params(params).and_then(|params| cut(&state, params)),
+        "find" => parse_params(params).and_then(|params| find(&state, params)),
+        "stopFinding" => parse_params(params).and_then(|params| stop_finding(&state, params)),
+        "setClipboard" => parse_params(params).and_then(|params| set_clipboard(&state, params)),
+        "setAudioMuted" => parse_params(params).and_then(|params| set_audio_muted(&state, params)),
+        "respondPermission" => {
+            parse_params(params).and_then(|params| respond_permission(&state, params))
+        }
         "delete" => parse_params(params).and_then(|params| delete(&state, params)),
         "continueFileDialog" => {
             parse_params(params).and_then(|params| continue_file_dialog(&state, params))
@@ -161,16 +219,51 @@ struct TabParams {
     tab: i32,
 }
 
+const MAX_DPR: f64 = 8.0;
+/// Directory in the cache directory that saved PDFs are written to.
+const DOWNLOADS_DIR: &str = "downloads";
+
 fn default_dpr() -> f64 {
     1.0
 }
 
+fn default_frame_rate() -> i32 {
+    MAX_FRAME_RATE
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenTabParams {
     url: String,
     wait_until_loaded: bool,
+    /// Size of the tab, the server-wide size is used when not set.
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
     #[serde(default = "default_dpr")]
     dpr: f64,
+    #[serde(default = "default_frame_rate")]
+    frame_rate: i32,
+    #[serde(default)]
+    adaptive_frame_rate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetFrameRateParams {
+    tab: i32,
+    frame_rate: i32,
+    #[serde(default)]
+    adaptive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetDevicePixelRatioParams {
+    tab: i32,
+    dpr: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -180,12 +273,38 @@ struct ResizeParams {
 }
 
 #[derive(Debug, Deserialize)]
-struct ScreenshotParams {
+struct ResizeTabParams {
     tab: i32,
     width: u32,
     height: u32,
 }
 
+#[derive(Debug, Deserialize)]
+struct ScreenshotParams {
+    tab: i32,
+    #[serde(flatten)]
+    options: ScreenshotOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrintToPdfParams {
+    tab: i32,
+    #[serde(flatten)]
+    options: PdfOptions,
+    /// Write the PDF into the `downloads` directory of the profile instead of returning it.
+    #[serde(default)]
+    save: bool,
+    #[serde(default)]
+    file_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetEmulationParams {
+    tab: i32,
+    #[serde(flatten)]
+    settings: EmulationSettings,
+}
+
 #[derive(Debug, Deserialize)]
 struct NavigateParams {
     tab: i32,
@@ -202,6 +321,31 @@ struct PositionParams {
     y: i32,
 }
 
+#[derive(Debug, Deserialize)]
+struct DragEnterParams {
+    tab: i32,
+    x: i32,
+    y: i32,
+    #[serde(flatten)]
+    data: DropData,
+}
+
+#[derive(Debug, Deserialize)]
+struct TouchParams {
+    tab: i32,
+    #[serde(flatten)]
+    point: TouchPoint,
+}
+
+#[derive(Debug, Deserialize)]
+struct MouseMoveParams {
+    tab: i32,
+    x: i32,
+    y: i32,
+    #[serde(default)]
+    leave: bool,
+}
+
 #[derive(Debug, Deserialize)]
 struct ClickParams {
     tab: i32,
@@ -209,6 +353,7 @@ struct ClickParams {
     y: i32,
     button: MouseButton,
     down: bool,
+    click_count: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -239,6 +384,89 @@ struct CharParams {
     unicode: u16,
 }
 
+#[derive(Debug, Deserialize)]
+struct SetZoomLevelParams {
+    tab: i32,
+    level: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FindParams {
+    tab: i32,
+    text: String,
+    #[serde(default = "default_true")]
+    forward: bool,
+    #[serde(default)]
+    match_case: bool,
+    #[serde(default)]
+    find_next: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopFindingParams {
+    tab: i32,
+    #[serde(default = "default_true")]
+    clear_selection: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetClipboardParams {
+    tab: i32,
+    #[serde(flatten)]
+    clipboard: ClipboardData,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetAudioMutedParams {
+    tab: i32,
+    muted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RespondPermissionParams {
+    tab: i32,
+    id: u64,
+    allow: bool,
+    #[serde(default = "default_true")]
+    remember: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TypeTextParams {
+    tab: i32,
+    text: String,
+    /// Milliseconds to wait after every character.
+    #[serde(default)]
+    delay: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PressParams {
+    tab: i32,
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImeSetCompositionParams {
+    tab: i32,
+    text: String,
+    selection: TextRange,
+    replacement: Option<TextRange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImeCommitTextParams {
+    tab: i32,
+    text: String,
+    replacement: Option<TextRange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImeFinishComposingTextParams {
+    tab: i32,
+    keep_selection: bool,
+}
+
 #[derive(Debug, Deserialize)]
 struct SetFocusParams {
     tab: i32,
@@ -282,6 +510,15 @@ fn parse_params<T: DeserializeOwned>(params: serde_json::Value) -> Result<T, ser
     })
 }
 
+fn check_size(width: u32, height: u32) -> Result<(u32, u32), serde_json::Value> {
+    if width == 0 || height == 0 {
+        return Err(json!({
+            "message": format!("invalid size: {}x{}", width, height)
+        }));
+    }
+    Ok((width, height))
+}
+
 async fn close(state: &SharedServerState) -> Result<serde_json::Value, serde_json::Value> {
     match state.lock().shutdown_tx.send(()) {
         Ok(_) => Ok(json!({ "success": true })),
@@ -296,14 +533,44 @@ async fn open_tab(
     state: &SharedServerState,
     params: OpenTabParams,
 ) -> Result<serde_json::Value, serde_json::Value> {
-    let (width, height) = { state.lock().size };
+    let size = match (params.width, params.height) {
+        (Some(width), Some(height)) => Some(check_size(width, height)?),
+        (None, None) => None,
+        _ => {
+            return Err(json!({
+                "message": "width and height must be set together"
+            }))
+        }
+    };
+
+    let (custom_size, (width, height), preferences) = {
+        let state = state.lock();
+        let (custom_size, size) = match size {
+            Some(size) if !state.use_server_size => (true, size),
+            _ => (false, state.size),
+        };
+        (custom_size, size, state.preferences.clone())
+    };
     info!(
         "[open_tab] size: ({}, {}), url: {}",
         width, height, params.url
     );
-    let mut tab = Browser::new(width, height, params.dpr, &params.url);
+    let mut tab = Browser::new(
+        width,
+        height,
+        params.dpr,
+        params.frame_rate,
+        &params.url,
+        preferences,
+    );
+    if params.adaptive_frame_rate {
+        tab.set_frame_rate(params.frame_rate, true);
+    }
     let id = tab.get_id();
     state.set_tab(id, tab.clone());
+    if custom_size {
+        state.lock().custom_sized_tabs.insert(id);
+    }
 
     if params.wait_until_loaded {
         match tab.automation.wait_until_loaded().await {
@@ -382,18 +649,43 @@ fn resize(
     state
         .tabs
         .iter()
+        .filter(|(id, _)| !state.custom_sized_tabs.contains(id))
         .for_each(|t| t.1.resize(params.width, params.height));
 
     Ok(json!({ "success": true }))
 }
 
+fn resize_tab(
+    state: &SharedServerState,
+    params: ResizeTabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    info!(
+        "[resize_tab] tab: {}, ({}, {})",
+        params.tab, params.width, params.height
+    );
+
+    check_size(params.width, params.height)?;
+    let tab = get_tab(state, params.tab)?;
+    let mut state = state.lock();
+    if state.use_server_size {
+        return Err(json!({
+            "message": "server size is used, cannot resize"
+        }));
+    }
+
+    state.custom_sized_tabs.insert(params.tab);
+    tab.resize(params.width, params.height);
+
+    Ok(json!({ "success": true }))
+}
+
 async fn screenshot(
     state: &SharedServerState,
     params: ScreenshotParams,
 ) -> Result<serde_json::Value, serde_json::Value> {
     let tab = get_tab(state, params.tab)?;
 
-    match tab.automation.screenshot(params.width, params.height).await {
+    match tab.automation.screenshot(&params.options).await {
         Ok(data) => Ok(json!({ "screenshot": data })),
         Err(e) => Err(json!({
             "message": format!("failed to take screenshot: {}", e)
@@ -401,6 +693,75 @@ async fn screenshot(
     }
 }
 
+async fn print_to_pdf(
+    state: &SharedServerState,
+    params: PrintToPdfParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+
+    let result = if params.save {
+        let file_name = params.file_name.as_deref().unwrap_or("page.pdf");
+        let dir = Path::new(&state.lock().cache_dir).join(DOWNLOADS_DIR);
+        tab.automation
+            .save_pdf(&params.options, &dir, file_name)
+            .await
+            .map(|path| json!({ "path": path }))
+    } else {
+        tab.automation
+            .print_to_pdf(&params.options)
+            .await
+            .map(|data| json!({ "pdf": data }))
+    };
+
+    result.map_err(|e| {
+        json!({
+            "message": format!("failed to print to PDF: {}", e)
+        })
+    })
+}
+
+async fn set_emulation(
+    state: &SharedServerState,
+    params: SetEmulationParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    info!(
+        "[tab: {}] set emulation: {:?}",
+        tab.get_id(),
+        params.settings
+    );
+
+    match tab.emulation.set(params.settings).await {
+        Ok(_) => Ok(json!({ "success": true })),
+        Err(e) => Err(json!({
+            "message": format!("failed to set emulation: {}", e)
+        })),
+    }
+}
+
+async fn clear_emulation(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    info!("[tab: {}] clear emulation", tab.get_id());
+
+    match tab.emulation.set(EmulationSettings::default()).await {
+        Ok(_) => Ok(json!({ "success": true })),
+        Err(e) => Err(json!({
+            "message": format!("failed to clear emulation: {}", e)
+        })),
+    }
+}
+
+fn get_emulation(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    Ok(json!({ "emulation": tab.emulation.get() }))
+}
+
 fn title(
     state: &SharedServerState,
     params: TabParams,
@@ -417,6 +778,74 @@ fn url(
     Ok(json!({ "url": tab.get_url() }))
 }
 
+fn zoom_level(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    Ok(json!({ "level": tab.zoom.level(), "factor": tab.zoom.factor() }))
+}
+
+fn set_zoom_level(
+    state: &SharedServerState,
+    params: SetZoomLevelParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    if !params.level.is_finite() {
+        return Err(json!({
+            "message": format!("invalid zoom level: {}", params.level)
+        }));
+    }
+
+    let tab = get_tab(state, params.tab)?;
+    tab.zoom.set_level(params.level);
+
+    Ok(json!({ "success": true }))
+}
+
+fn zoom_in(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.zoom.zoom_in();
+
+    Ok(json!({ "success": true }))
+}
+
+fn zoom_out(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.zoom.zoom_out();
+
+    Ok(json!({ "success": true }))
+}
+
+fn reset_zoom(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.zoom.reset();
+
+    Ok(json!({ "success": true }))
+}
+
+fn subscriber_stats(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    let subscribers = tab
+        .subscriber_stats()
+        .into_iter()
+        .map(|(id, stats)| json!({ "id": id, "stats": stats }))
+        .collect::<Vec<_>>();
+
+    Ok(json!({ "subscribers": subscribers }))
+}
+
 async fn navigate(
     state: &SharedServerState,
     params: NavigateParams,
@@ -442,10 +871,10 @@ async fn navigate(
 
 fn mouse_move(
     state: &SharedServerState,
-    params: PositionParams,
+    params: MouseMoveParams,
 ) -> Result<serde_json::Value, serde_json::Value> {
     let tab = get_tab(state, params.tab)?;
-    tab.mouse.move_to(params.x, params.y);
+    tab.mouse.move_to(params.x, params.y, params.leave);
 
     Ok(json!({ "success": true }))
 }
@@ -455,8 +884,67 @@ fn click(
     params: ClickParams,
 ) -> Result<serde_json::Value, serde_json::Value> {
     let tab = get_tab(state, params.tab)?;
-    tab.mouse
-        .click(params.x, params.y, params.button, params.down);
+    tab.mouse.click(
+        params.x,
+        params.y,
+        params.button,
+        params.down,
+        params.click_count,
+    );
+
+    Ok(json!({ "success": true }))
+}
+
+fn touch(
+    state: &SharedServerState,
+    params: TouchParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.touch.send(&params.point);
+
+    Ok(json!({ "success": true }))
+}
+
+fn drag_enter(
+    state: &SharedServerState,
+    params: DragEnterParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+
+    match tab.drag.enter(params.x, params.y, params.data) {
+        Ok(_) => Ok(json!({ "success": true })),
+        Err(e) => Err(json!({
+            "message": format!("failed to start dragging: {}", e)
+        })),
+    }
+}
+
+fn drag_over(
+    state: &SharedServerState,
+    params: PositionParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.drag.over(params.x, params.y);
+
+    Ok(json!({ "success": true }))
+}
+
+fn drag_leave(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.drag.leave();
+
+    Ok(json!({ "success": true }))
+}
+
+fn drop_data(
+    state: &SharedServerState,
+    params: PositionParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.drag.drop_at(params.x, params.y);
 
     Ok(json!({ "success": true }))
 }
@@ -500,6 +988,75 @@ fn char(
     Ok(json!({ "success": true }))
 }
 
+async fn type_text(
+    state: &SharedServerState,
+    params: TypeTextParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.keyboard
+        .type_text(&params.text, Duration::from_millis(params.delay))
+        .await;
+
+    Ok(json!({ "success": true }))
+}
+
+fn press(
+    state: &SharedServerState,
+    params: PressParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+
+    match tab.keyboard.press(&params.key) {
+        Ok(_) => Ok(json!({ "success": true })),
+        Err(e) => Err(json!({
+            "message": format!("failed to press {}: {}", params.key, e)
+        })),
+    }
+}
+
+fn ime_set_composition(
+    state: &SharedServerState,
+    params: ImeSetCompositionParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.keyboard
+        .ime_set_composition(&params.text, params.selection, params.replacement);
+
+    Ok(json!({ "success": true }))
+}
+
+fn ime_commit_text(
+    state: &SharedServerState,
+    params: ImeCommitTextParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.keyboard
+        .ime_commit_text(&params.text, params.replacement);
+
+    Ok(json!({ "success": true }))
+}
+
+fn ime_finish_composing_text(
+    state: &SharedServerState,
+    params: ImeFinishComposingTextParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.keyboard
+        .ime_finish_composing_text(params.keep_selection);
+
+    Ok(json!({ "success": true }))
+}
+
+fn ime_cancel_composition(
+    state: &SharedServerState,
+    params: TabParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.keyboard.ime_cancel_composition();
+
+    Ok(json!({ "success": true }))
+}
+
 fn stop_video(
     state: &SharedServerState,
     params: TabParams,
@@ -522,6 +1079,44 @@ fn start_video(
     Ok(json!({ "success": true }))
 }
 
+fn set_frame_rate(
+    state: &SharedServerState,
+    params: SetFrameRateParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    info!(
+        "[tab: {}] set frame rate: {} (adaptive: {})",
+        tab.get_id(),
+        params.frame_rate,
+        params.adaptive
+    );
+    tab.set_frame_rate(params.frame_rate, params.adaptive);
+
+    let (frame_rate, adaptive) = tab.get_frame_rate();
+    Ok(json!({ "frame_rate": frame_rate, "adaptive": adaptive }))
+}
+
+fn set_device_pixel_ratio(
+    state: &SharedServerState,
+    params: SetDevicePixelRatioParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    if !params.dpr.is_finite() || params.dpr <= 0.0 || params.dpr > MAX_DPR {
+        return Err(json!({
+            "message": format!("invalid device pixel ratio: {}", params.dpr)
+        }));
+    }
+
+    let tab = get_tab(state, params.tab)?;
+    info!(
+        "[tab: {}] set device pixel ratio: {}",
+        tab.get_id(),
+        params.dpr
+    );
+    tab.set_device_pixel_ratio(params.dpr);
+
+    Ok(json!({ "success": true }))
+}
+
 async fn reload(
     state: &SharedServerState,
     params: NavigateParams,
@@ -702,6 +1297,65 @@ fn cut(
     Ok(json!({ "success": true }))
 }
 
+fn find(
+    state: &SharedServerState,
+    params: FindParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.find(
+        &params.text,
+        params.forward,
+        params.match_case,
+        params.find_next,
+    );
+
+    Ok(json!({ "success": true }))
+}
+
+fn stop_finding(
+    state: &SharedServerState,
+    params: StopFindingParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.stop_finding(params.clear_selection);
+
+    Ok(json!({ "success": true }))
+}
+
+fn set_clipboard(
+    state: &SharedServerState,
+    params: SetClipboardParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.set_clipboard(params.clipboard);
+
+    Ok(json!({ "success": true }))
+}
+
+fn set_audio_muted(
+    state: &SharedServerState,
+    params: SetAudioMutedParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+    tab.set_audio_muted(params.muted);
+
+    Ok(json!({ "success": true }))
+}
+
+fn respond_permission(
+    state: &SharedServerState,
+    params: RespondPermissionParams,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let tab = get_tab(state, params.tab)?;
+
+    match tab.respond_permission(params.id, params.allow, params.remember) {
+        Ok(_) => Ok(json!({ "success": true })),
+        Err(e) => Err(json!({
+            "message": format!("failed to respond to permission request: {}", e)
+        })),
+    }
+}
+
 fn continue_file_dialog(
     state: &SharedServerState,
     params: ContinueFileDialogParams,
@@ -0,0 +1,606 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use huly_cef::{
+    DirtyRect, Framebuffer, PopupLayer, FRAME_FORMAT_VERSION, FRAME_HEADER_SIZE, FRAME_MAGIC,
+    FRAME_RECT_HEADER_SIZE, PIXEL_FORMAT_RGBA8,
+};
+use image::{
+    codecs::{
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType, PngEncoder},
+        qoi::QoiEncoder,
+        webp::WebPEncoder,
+    },
+    ExtendedColorType, ImageEncoder,
+};
+use log::warn;
+
+use crate::server::audio::AudioFormat;
+
+const KEYFRAME_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_QUALITY: u8 = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum FrameKind {
+    Key = 0,
+    Delta = 1,
+    Popup = 2,
+}
+
+/// Header of a binary frame, see [`FRAME_FORMAT_VERSION`] for the layout.
+struct FrameHeader {
+    kind: FrameKind,
+    sequence: u64,
+    timestamp: u64,
+    dpr: f64,
+    width: u32,
+    height: u32,
+    rect_count: usize,
+}
+
+/// Pixel encoding of the rects in a binary frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Encoding {
+    #[default]
+    Raw = 0,
+    Png = 1,
+    Jpeg = 2,
+    /// Lossless WebP, the quality setting is ignored.
+    WebP = 3,
+    Qoi = 4,
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "raw" => Ok(Encoding::Raw),
+            "png" => Ok(Encoding::Png),
+            "jpeg" | "jpg" => Ok(Encoding::Jpeg),
+            "webp" => Ok(Encoding::WebP),
+            "qoi" => Ok(Encoding::Qoi),
+            _ => Err(anyhow::anyhow!("unknown encoding: {}", s)),
+        }
+    }
+}
+
+/// How frames reach the client.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Binary frame messages over the tab socket.
+    #[default]
+    WebSocket,
+    /// Frames are written into a shared-memory ring and the tab socket carries
+    /// `FrameReady` notifications. Only works for clients on the same host.
+    SharedMemory,
+}
+
+impl FromStr for Transport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ws" | "websocket" => Ok(Transport::WebSocket),
+            "shm" => Ok(Transport::SharedMemory),
+            _ => Err(anyhow::anyhow!("unknown transport: {}", s)),
+        }
+    }
+}
+
+/// Frame settings a client negotiates when it opens `/tab/<id>`,
+/// e.g. `/tab/1?encoding=jpeg&quality=70` or `/tab/1?transport=shm`.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameOptions {
+    pub encoding: Encoding,
+    pub quality: u8,
+    pub transport: Transport,
+    /// Audio is only streamed to clients that ask for it, e.g. `/tab/1?audio=s16`.
+    pub audio: Option<AudioFormat>,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self {
+            encoding: Encoding::Raw,
+            quality: DEFAULT_QUALITY,
+            transport: Transport::WebSocket,
+            audio: None,
+        }
+    }
+}
+
+impl FrameOptions {
+    pub fn from_query(query: Option<&str>) -> Self {
+        let mut options = FrameOptions::default();
+        for (key, value) in query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+        {
+            match key {
+                "encoding" => match value.parse() {
+                    Ok(encoding) => options.encoding = encoding,
+                    Err(e) => warn!("{}, using raw frames", e),
+                },
+                "quality" => match value.parse::<u8>() {
+                    Ok(quality) => options.quality = quality.clamp(1, 100),
+                    Err(_) => warn!("invalid quality: {}, using {}", value, options.quality),
+                },
+                "transport" => match value.parse() {
+                    Ok(transport) => options.transport = transport,
+                    Err(e) => warn!("{}, using the tab socket", e),
+                },
+                "audio" => match value.parse() {
+                    Ok(format) => options.audio = Some(format),
+                    Err(e) => warn!("{}, not sending audio", e),
+                },
+                _ => {}
+            }
+        }
+        options
+    }
+}
+
+/// Pixels of a frame update, copied out of the framebuffer by [`FrameEncoder::snapshot`].
+pub struct FrameSnapshot {
+    kind: FrameKind,
+    dpr: f64,
+    width: u32,
+    height: u32,
+    rects: Vec<DirtyRect>,
+    /// RGBA pixels of `rects`, one rect after another.
+    data: Vec<u8>,
+}
+
+/// Turns framebuffer updates into binary messages, sending only the dirty regions
+/// unless a full keyframe is due.
+pub struct FrameEncoder {
+    options: FrameOptions,
+    size: (u32, u32),
+    last_keyframe: Option<Instant>,
+    keyframe_requested: bool,
+    pixels: Vec<u8>,
+}
+
+impl FrameEncoder {
+    pub fn new(options: FrameOptions) -> Self {
+        Self {
+            options,
+            size: (0, 0),
+            last_keyframe: None,
+            keyframe_requested: true,
+            pixels: Vec::new(),
+        }
+    }
+
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_requested = true;
+    }
+
+    /// Copies the pixels of `dirty_rects`, or of the whole frame when a keyframe is due.
+    /// Call it with the framebuffer locked and encode the snapshot after releasing it,
+    /// so that painting isn't blocked while the pixels are compressed.
+    pub fn snapshot(&mut self, frame: &Framebuffer, dirty_rects: &[DirtyRect]) -> FrameSnapshot {
+        let (kind, rects) = if self.is_keyframe_due(frame, dirty_rects) {
+            self.size = (frame.width, frame.height);
+            self.last_keyframe = Some(Instant::now());
+            self.keyframe_requested = false;
+            let full = DirtyRect {
+                x: 0,
+                y: 0,
+                width: frame.width,
+                height: frame.height,
+            };
+            (FrameKind::Key, vec![full])
+        } else {
+            (FrameKind::Delta, dirty_rects.to_vec())
+        };
+
+        let stride = frame.width as usize * 4;
+        let mut data = Vec::with_capacity(rects.iter().map(DirtyRect::area).sum::<usize>() * 4);
+        for rect in &rects {
+            for row in rect.y..rect.y + rect.height {
+                let start = row as usize * stride + rect.x as usize * 4;
+                data.extend_from_slice(&frame.data[start..start + rect.width as usize * 4]);
+            }
+        }
+
+        FrameSnapshot {
+            kind,
+            dpr: frame.dpr,
+            width: frame.width,
+            height: frame.height,
+            rects,
+            data,
+        }
+    }
+
+    pub fn encode(
+        &mut self,
+        snapshot: &FrameSnapshot,
+        sequence: u64,
+        timestamp: u64,
+        buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        let header = FrameHeader {
+            kind: snapshot.kind,
+            sequence,
+            timestamp,
+            dpr: snapshot.dpr,
+            width: snapshot.width,
+            height: snapshot.height,
+            rect_count: snapshot.rects.len(),
+        };
+        self.write_header(buffer, &header);
+
+        let mut offset = 0;
+        for rect in &snapshot.rects {
+            let source = DirtyRect {
+                x: 0,
+                y: 0,
+                width: rect.width,
+                height: rect.height,
+            };
+            let data = &snapshot.data[offset..offset + rect.area() * 4];
+            offset += data.len();
+            self.write_rect(buffer, rect, |encoder, out| {
+                encoder.encode_rect(data, rect.width, &source, out)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn encode_popup(&mut self, popup: &PopupLayer, buffer: &mut Vec<u8>) -> Result<()> {
+        let (width, height) = (popup.rect.width, popup.rect.height);
+        let source = DirtyRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+
+        let header = FrameHeader {
+            kind: FrameKind::Popup,
+            sequence: popup.sequence,
+            timestamp: popup.timestamp,
+            dpr: popup.dpr,
+            width,
+            height,
+            rect_count: 1,
+        };
+        self.write_header(buffer, &header);
+        self.write_rect(buffer, &popup.rect, |encoder, out| {
+            encoder.encode_rect(&popup.data, width, &source, out)
+        })
+    }
+
+    fn write_header(&self, buffer: &mut Vec<u8>, header: &FrameHeader) {
+        buffer.clear();
+        buffer.reserve(FRAME_HEADER_SIZE + header.rect_count * FRAME_RECT_HEADER_SIZE);
+        buffer.extend_from_slice(&FRAME_MAGIC);
+        buffer.push(FRAME_FORMAT_VERSION);
+        buffer.push(header.kind as u8);
+        buffer.push(self.options.encoding as u8);
+        buffer.push(PIXEL_FORMAT_RGBA8);
+        buffer.extend_from_slice(&header.sequence.to_le_bytes());
+        buffer.extend_from_slice(&header.timestamp.to_le_bytes());
+        buffer.extend_from_slice(&(header.dpr as f32).to_le_bytes());
+        buffer.extend_from_slice(&header.width.to_le_bytes());
+        buffer.extend_from_slice(&header.height.to_le_bytes());
+        buffer.extend_from_slice(&(header.rect_count as u32).to_le_bytes());
+    }
+
+    fn write_rect<F>(&mut self, buffer: &mut Vec<u8>, rect: &DirtyRect, encode: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self, &mut Vec<u8>) -> Result<()>,
+    {
+        buffer.extend_from_slice(&rect.x.to_le_bytes());
+        buffer.extend_from_slice(&rect.y.to_le_bytes());
+        buffer.extend_from_slice(&rect.width.to_le_bytes());
+        buffer.extend_from_slice(&rect.height.to_le_bytes());
+
+        let length_offset = buffer.len();
+        buffer.extend_from_slice(&0u32.to_le_bytes());
+        encode(self, buffer)?;
+
+        let length = (buffer.len() - length_offset - 4) as u32;
+        buffer[length_offset..length_offset + 4].copy_from_slice(&length.to_le_bytes());
+        Ok(())
+    }
+
+    /// Encodes `rect` of an RGBA image `width` pixels wide.
+    fn encode_rect(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        rect: &DirtyRect,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        let stride = width as usize * 4;
+        let rows = (rect.y..rect.y + rect.height).map(|row| {
+            let start = row as usize * stride + rect.x as usize * 4;
+            &data[start..start + rect.width as usize * 4]
+        });
+
+        if self.options.encoding == Encoding::Raw {
+            rows.for_each(|row| out.extend_from_slice(row));
+            return Ok(());
+        }
+
+        self.pixels.clear();
+        if self.options.encoding == Encoding::Jpeg {
+            for row in rows {
+                for pixel in row.chunks_exact(4) {
+                    self.pixels.extend_from_slice(&pixel[..3]);
+                }
+            }
+        } else {
+            rows.for_each(|row| self.pixels.extend_from_slice(row));
+        }
+
+        let (w, h) = (rect.width, rect.height);
+        match self.options.encoding {
+            Encoding::Raw => unreachable!(),
+            Encoding::Png => {
+                PngEncoder::new_with_quality(out, CompressionType::Fast, FilterType::Adaptive)
+                    .write_image(&self.pixels, w, h, ExtendedColorType::Rgba8)?
+            }
+            Encoding::Jpeg => JpegEncoder::new_with_quality(out, self.options.quality)
+                .write_image(&self.pixels, w, h, ExtendedColorType::Rgb8)?,
+            Encoding::WebP => WebPEncoder::new_lossless(out).write_image(
+                &self.pixels,
+                w,
+                h,
+                ExtendedColorType::Rgba8,
+            )?,
+            Encoding::Qoi => {
+                QoiEncoder::new(out).write_image(&self.pixels, w, h, ExtendedColorType::Rgba8)?
+            }
+        }
+
+        Ok(())
+    }
+
+    // Option::is_none_or needs Rust 1.82.
+    #[allow(clippy::unnecessary_map_or)]
+    fn is_keyframe_due(&self, frame: &Framebuffer, dirty_rects: &[DirtyRect]) -> bool {
+        if self.keyframe_requested || self.size != (frame.width, frame.height) {
+            return true;
+        }
+
+        if self
+            .last_keyframe
+            .map_or(true, |t| t.elapsed() >= KEYFRAME_INTERVAL)
+        {
+            return true;
+        }
+
+        let frame_area = frame.width as usize * frame.height as usize;
+        let dirty_area = dirty_rects.iter().map(DirtyRect::area).sum::<usize>();
+        dirty_area >= frame_area
+            || dirty_rects
+                .iter()
+                .any(|r| r.x + r.width > frame.width || r.y + r.height > frame.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            data: vec![0; width as usize * height as usize * 4],
+            ..Default::default()
+        }
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> DirtyRect {
+        DirtyRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn encode(encoder: &mut FrameEncoder, frame: &Framebuffer, dirty_rects: &[DirtyRect]) {
+        let snapshot = encoder.snapshot(frame, dirty_rects);
+        encoder.encode(&snapshot, 0, 0, &mut Vec::new()).unwrap();
+    }
+
+    fn encode_rect(encoder: &mut FrameEncoder, frame: &Framebuffer, rect: &DirtyRect) -> Vec<u8> {
+        let mut out = Vec::new();
+        encoder
+            .encode_rect(&frame.data, frame.width, rect, &mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn options_from_query() {
+        let options = FrameOptions::from_query(Some("encoding=jpeg&quality=70"));
+        assert_eq!(options.encoding, Encoding::Jpeg);
+        assert_eq!(options.quality, 70);
+
+        let options = FrameOptions::from_query(Some("quality=0&encoding=QOI"));
+        assert_eq!(options.encoding, Encoding::Qoi);
+        assert_eq!(options.quality, 1);
+
+        let options = FrameOptions::from_query(Some("transport=shm"));
+        assert_eq!(options.transport, Transport::SharedMemory);
+
+        let options = FrameOptions::from_query(Some("audio=s16"));
+        assert_eq!(options.audio, Some(AudioFormat::S16));
+    }
+
+    #[test]
+    fn invalid_options_fall_back_to_defaults() {
+        for query in [
+            None,
+            Some(""),
+            Some("encoding=gif&quality=high&transport=pipe&audio=mp3&unknown=1&flag"),
+        ] {
+            let options = FrameOptions::from_query(query);
+            assert_eq!(options.encoding, Encoding::Raw);
+            assert_eq!(options.quality, DEFAULT_QUALITY);
+            assert_eq!(options.transport, Transport::WebSocket);
+            assert_eq!(options.audio, None);
+        }
+    }
+
+    #[test]
+    fn header_layout() {
+        let mut encoder = FrameEncoder::new(FrameOptions {
+            encoding: Encoding::Qoi,
+            ..Default::default()
+        });
+        let mut frame = frame(4, 2);
+        frame.dpr = 2.0;
+        let snapshot = encoder.snapshot(&frame, &[]);
+        let mut buffer = Vec::new();
+        encoder
+            .encode(&snapshot, 7, 1_000_000, &mut buffer)
+            .unwrap();
+
+        assert_eq!(buffer[..4], FRAME_MAGIC);
+        assert_eq!(buffer[4], FRAME_FORMAT_VERSION);
+        assert_eq!(buffer[5], FrameKind::Key as u8);
+        assert_eq!(buffer[6], Encoding::Qoi as u8);
+        assert_eq!(buffer[7], PIXEL_FORMAT_RGBA8);
+        assert_eq!(buffer[8..16], 7u64.to_le_bytes());
+        assert_eq!(buffer[16..24], 1_000_000u64.to_le_bytes());
+        assert_eq!(buffer[24..28], 2.0f32.to_le_bytes());
+        assert_eq!(buffer[28..32], 4u32.to_le_bytes());
+        assert_eq!(buffer[32..36], 2u32.to_le_bytes());
+        assert_eq!(buffer[36..40], 1u32.to_le_bytes());
+
+        // A keyframe has a single rect covering the whole frame.
+        let rect = [0u32, 0, 4, 2]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let payload = &buffer[FRAME_HEADER_SIZE + FRAME_RECT_HEADER_SIZE..];
+        assert_eq!(buffer[40..56], rect[..]);
+        assert_eq!(buffer[56..60], (payload.len() as u32).to_le_bytes());
+    }
+
+    #[test]
+    fn rects_are_encoded_as_images() {
+        let mut frame = frame(4, 4);
+        for (i, pixel) in frame.data.chunks_exact_mut(4).enumerate() {
+            pixel.copy_from_slice(&[i as u8, 0, 0, 255]);
+        }
+        let rect = rect(1, 1, 2, 2);
+        let expected = [5, 0, 0, 255, 6, 0, 0, 255, 9, 0, 0, 255, 10, 0, 0, 255];
+
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        assert_eq!(encode_rect(&mut encoder, &frame, &rect), expected);
+
+        for encoding in [Encoding::Png, Encoding::WebP, Encoding::Qoi] {
+            let mut encoder = FrameEncoder::new(FrameOptions {
+                encoding,
+                ..Default::default()
+            });
+            let image = image::load_from_memory(&encode_rect(&mut encoder, &frame, &rect))
+                .unwrap()
+                .to_rgba8();
+            assert_eq!(image.dimensions(), (2, 2), "{:?}", encoding);
+            assert_eq!(image.as_raw()[..], expected, "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn delta_frames_hold_the_dirty_rects() {
+        let mut frame = frame(4, 4);
+        for (i, pixel) in frame.data.chunks_exact_mut(4).enumerate() {
+            pixel.copy_from_slice(&[i as u8, 0, 0, 255]);
+        }
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        encode(&mut encoder, &frame, &[]);
+
+        let snapshot = encoder.snapshot(&frame, &[rect(1, 1, 2, 2), rect(3, 3, 1, 1)]);
+        frame.data.fill(0);
+        let mut buffer = Vec::new();
+        encoder.encode(&snapshot, 0, 0, &mut buffer).unwrap();
+
+        assert_eq!(buffer[5], FrameKind::Delta as u8);
+        assert_eq!(buffer[36..40], 2u32.to_le_bytes());
+        let first = FRAME_HEADER_SIZE + FRAME_RECT_HEADER_SIZE;
+        assert_eq!(
+            buffer[first..first + 16],
+            [5, 0, 0, 255, 6, 0, 0, 255, 9, 0, 0, 255, 10, 0, 0, 255]
+        );
+        assert_eq!(
+            buffer[first + 16 + FRAME_RECT_HEADER_SIZE..],
+            [15, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn first_frame_is_a_keyframe() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        let frame = frame(100, 100);
+        assert!(encoder.is_keyframe_due(&frame, &[rect(0, 0, 10, 10)]));
+
+        encode(&mut encoder, &frame, &[rect(0, 0, 10, 10)]);
+        assert!(!encoder.is_keyframe_due(&frame, &[rect(0, 0, 10, 10)]));
+    }
+
+    #[test]
+    fn keyframe_on_request() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        let frame = frame(100, 100);
+        encode(&mut encoder, &frame, &[]);
+
+        encoder.request_keyframe();
+        assert!(encoder.is_keyframe_due(&frame, &[rect(0, 0, 10, 10)]));
+    }
+
+    #[test]
+    fn keyframe_on_resize() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        encode(&mut encoder, &frame(100, 100), &[]);
+
+        assert!(encoder.is_keyframe_due(&frame(200, 100), &[rect(0, 0, 10, 10)]));
+    }
+
+    #[test]
+    fn keyframe_when_the_whole_frame_is_dirty() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        let frame = frame(100, 100);
+        encode(&mut encoder, &frame, &[]);
+
+        let halves = [rect(0, 0, 100, 50), rect(0, 50, 100, 50)];
+        assert!(encoder.is_keyframe_due(&frame, &halves));
+    }
+
+    #[test]
+    fn keyframe_for_rects_outside_the_frame() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        let frame = frame(100, 100);
+        encode(&mut encoder, &frame, &[]);
+
+        assert!(encoder.is_keyframe_due(&frame, &[rect(90, 90, 20, 20)]));
+    }
+
+    #[test]
+    fn keyframe_after_the_interval() {
+        let mut encoder = FrameEncoder::new(FrameOptions::default());
+        let frame = frame(100, 100);
+        encode(&mut encoder, &frame, &[]);
+
+        encoder.last_keyframe = Instant::now().checked_sub(KEYFRAME_INTERVAL);
+        assert!(encoder.is_keyframe_due(&frame, &[rect(0, 0, 10, 10)]));
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    path::Path,
     sync::{Arc, Mutex},
 };
 
@@ -9,9 +10,13 @@ use tokio::{
     sync::{broadcast, oneshot},
 };
 
-use huly_cef::browser::Browser;
+use huly_cef::browser::{preferences::Preferences, Browser};
 
+use crate::server::frame::FrameOptions;
+
+mod audio;
 mod browser;
+mod frame;
 mod tab;
 
 pub const WIDTH: u32 = 1280;
@@ -19,17 +24,19 @@ pub const HEIGHT: u32 = 720;
 
 enum ConnectionType {
     Browser,
-    Tab(i32),
+    Tab(i32, FrameOptions),
     None,
 }
 
 struct ServerState {
-    #[allow(dead_code)]
     cache_dir: String,
     tabs: HashMap<i32, Browser>,
+    preferences: Arc<Preferences>,
 
     use_server_size: bool,
     size: (u32, u32),
+    /// Tabs sized on their own, the server-wide resize leaves them alone.
+    custom_sized_tabs: HashSet<i32>,
 
     shutdown_tx: broadcast::Sender<()>,
 }
@@ -44,11 +51,14 @@ impl Clone for SharedServerState {
 
 impl SharedServerState {
     fn new(cache_dir: String, use_server_size: bool, shutdown_tx: broadcast::Sender<()>) -> Self {
+        let preferences = Arc::new(Preferences::load(Path::new(&cache_dir)));
         Self(Arc::new(Mutex::new(ServerState {
             cache_dir,
             tabs: HashMap::new(),
+            preferences,
             use_server_size,
             size: (WIDTH, HEIGHT),
+            custom_sized_tabs: HashSet::new(),
             shutdown_tx,
         })))
     }
@@ -69,6 +79,7 @@ impl SharedServerState {
 
     fn remove_tab(&self, id: i32) -> Option<Browser> {
         let mut state = self.0.lock().unwrap();
+        state.custom_sized_tabs.remove(&id);
         state.tabs.remove(&id)
     }
 }
@@ -122,7 +133,8 @@ async fn process_connection(stream: TcpStream, state: &SharedServerState) {
                     .strip_prefix("/tab/")
                     .and_then(|s| s.parse::<i32>().ok())
                 {
-                    connection_type = ConnectionType::Tab(id);
+                    let options = FrameOptions::from_query(req.uri().query());
+                    connection_type = ConnectionType::Tab(id, options);
                 } else {
                     error!("Invalid path for tab connection: {}", req.uri().path());
                 }
@@ -145,11 +157,11 @@ async fn process_connection(stream: TcpStream, state: &SharedServerState) {
             info!("new browser connection established");
             tokio::spawn(browser::handle(state.clone(), ws));
         }
-        ConnectionType::Tab(id) => {
-            info!("new tab connection established");
+        ConnectionType::Tab(id, options) => {
+            info!("new tab connection established: {:?}", options);
 
             match state.get_tab(id) {
-                Some(tab) => tokio::spawn(tab::event_loop(tab, ws)),
+                Some(tab) => tokio::spawn(tab::event_loop(tab, ws, options)),
                 None => {
                     error!("tab with id {} not found", id);
                     return;
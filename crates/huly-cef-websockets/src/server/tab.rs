@@ -1,54 +1,154 @@
-use futures::SinkExt;
-use huly_cef::{browser::Browser, Framebuffer, TabMessage};
-use log::{error, info};
-use tokio::{
-    net::TcpStream,
-    sync::mpsc::{self, UnboundedSender},
+use std::collections::HashSet;
+
+use futures::{SinkExt, StreamExt};
+use huly_cef::{
+    browser::Browser,
+    subscriber::{self, SubscriberSender},
+    TabMessage, ZoomChanged,
 };
+use log::{error, info};
+use serde::Deserialize;
+use tokio::net::TcpStream;
 use tokio_tungstenite::WebSocketStream;
 use tungstenite::Message;
 
-fn serialize(frame: &Framebuffer, buffer: &mut Vec<u8>) {
-    let width_bytes = 4;
-    let height_bytes = 4;
-
-    if buffer.len() != frame.data.len() + width_bytes + height_bytes {
-        buffer.resize(frame.data.len() + width_bytes + height_bytes, 0);
-    }
+use crate::server::{
+    audio::AudioEncoder,
+    frame::{FrameEncoder, FrameOptions, Transport},
+};
 
-    buffer[0..width_bytes].copy_from_slice(&frame.width.to_le_bytes());
-    buffer[width_bytes..width_bytes + height_bytes].copy_from_slice(&frame.height.to_le_bytes());
-    buffer[width_bytes + height_bytes..].copy_from_slice(&frame.data);
+/// Messages a client can send over the tab socket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum TabRequest {
+    /// Asks the server to send a full keyframe with the next frame.
+    Keyframe,
 }
 
-pub async fn event_loop(mut tab: Browser, mut websocket: WebSocketStream<TcpStream>) {
-    let (tx, mut rx) = mpsc::unbounded_channel();
+pub async fn event_loop(
+    mut tab: Browser,
+    websocket: WebSocketStream<TcpStream>,
+    options: FrameOptions,
+) {
+    let (tx, mut rx) = subscriber::channel();
+    let shared_frames = options.transport == Transport::SharedMemory
+        && match tab.enable_shared_frames() {
+            Ok(path) => {
+                info!("writing frames of tab {} into {}", tab.get_id(), path);
+                tx.use_shared_frames(true);
+                true
+            }
+            Err(e) => {
+                error!(
+                    "failed to set up shared frames, using the tab socket: {:?}",
+                    e
+                );
+                false
+            }
+        };
+    tx.enable_audio(options.audio.is_some());
     let id = tab.subscribe(tx.clone());
     generate_events(&tab, tx);
+    tab.invalidate();
 
+    let (mut sink, mut stream) = websocket.split();
+    let mut audio = options.audio.map(AudioEncoder::new);
+    let mut encoder = FrameEncoder::new(options);
     let mut buffer = Vec::new();
-    while let Some(message) = rx.recv().await {
+    let mut sent_cursors = HashSet::new();
+    loop {
+        let message = tokio::select! {
+            message = rx.recv() => match message {
+                Some(message) => message,
+                None => break,
+            },
+            request = stream.next() => match request {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<TabRequest>(text.as_str()) {
+                        Ok(TabRequest::Keyframe) => {
+                            encoder.request_keyframe();
+                            tab.invalidate();
+                        }
+                        Err(e) => error!("failed to deserialize tab request {}: {}", text, e),
+                    }
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    tab.unsubscribe(id);
+                    break;
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    error!("failed to read a message: {:?}", e);
+                    tab.unsubscribe(id);
+                    break;
+                }
+            },
+        };
+
+        if let (Some(audio), TabMessage::AudioStarted(_) | TabMessage::AudioStopped) =
+            (audio.as_mut(), &message)
+        {
+            audio.reset();
+        }
+
         let message = match message {
-            TabMessage::Frame(data) => {
-                let frame = data.lock().unwrap();
-                serialize(&frame, &mut buffer);
+            TabMessage::Frame {
+                framebuffer,
+                dirty_rects,
+                sequence,
+                timestamp,
+            } => {
+                let snapshot = encoder.snapshot(&framebuffer.lock().unwrap(), &dirty_rects);
+                if let Err(e) = encoder.encode(&snapshot, sequence, timestamp, &mut buffer) {
+                    error!("failed to encode a frame: {:?}", e);
+                    continue;
+                }
+                Message::Binary(buffer.clone().into())
+            }
+            TabMessage::Popup(popup) => {
+                if let Err(e) = encoder.encode_popup(&popup, &mut buffer) {
+                    error!("failed to encode a popup: {:?}", e);
+                    continue;
+                }
                 Message::Binary(buffer.clone().into())
             }
+            TabMessage::AudioPacket(packet) => {
+                let Some(audio) = audio.as_mut() else {
+                    continue;
+                };
+                if !audio.encode(&packet, &mut buffer) {
+                    continue;
+                }
+                Message::Binary(buffer.clone().into())
+            }
+            TabMessage::CustomCursor(mut cursor) => {
+                if !sent_cursors.insert(cursor.hash.clone()) {
+                    cursor.image = None;
+                }
+                serde_json::to_string(&TabMessage::CustomCursor(cursor))
+                    .expect("failed to serialize a message")
+                    .into()
+            }
             TabMessage::Closed => break,
             message => serde_json::to_string(&message)
                 .expect("failed to serialize a message")
                 .into(),
         };
 
-        if let Err(e) = websocket.send(message).await {
+        if let Err(e) = sink.send(message).await {
             error!("failed to send message: {:?}", e);
             tab.unsubscribe(id);
             break;
         }
     }
+
+    if shared_frames {
+        tab.disable_shared_frames();
+    }
 }
 
-pub fn generate_events(tab: &Browser, tx: UnboundedSender<TabMessage>) {
+pub fn generate_events(tab: &Browser, tx: SubscriberSender) {
     _ = tx.send(TabMessage::Url(tab.state.read(|state| state.url.clone())));
     _ = tx.send(TabMessage::Title(
         tab.state.read(|state| state.title.clone()),
@@ -56,6 +156,9 @@ pub fn generate_events(tab: &Browser, tx: UnboundedSender<TabMessage>) {
     _ = tx.send(TabMessage::Cursor(
         tab.state.read(|state| state.cursor.clone()),
     ));
+    if let Some(cursor) = tab.state.read(|state| state.custom_cursor.clone()) {
+        _ = tx.send(TabMessage::CustomCursor(cursor));
+    }
     _ = tx.send(TabMessage::LoadState(
         tab.state.read(|state| state.load_state.clone()),
     ));
@@ -67,5 +170,34 @@ pub fn generate_events(tab: &Browser, tx: UnboundedSender<TabMessage>) {
         _ = tx.send(TabMessage::Favicon(favicon.clone()));
     }
 
+    if let Some(popup) = tab.state.read(|state| state.popup.clone()) {
+        _ = tx.send(TabMessage::Popup(popup));
+    }
+
+    if let Some(audio) = tab.state.read(|state| state.audio.clone()) {
+        _ = tx.send(TabMessage::AudioStarted(audio));
+    }
+    _ = tx.send(TabMessage::Audible(tab.state.read(|state| state.audible)));
+    _ = tx.send(TabMessage::AudioMuted(
+        tab.state.read(|state| state.audio_muted),
+    ));
+
+    for request in tab.state.read(|state| {
+        state
+            .permission_requests
+            .values()
+            .map(|pending| pending.request.clone())
+            .collect::<Vec<_>>()
+    }) {
+        _ = tx.send(TabMessage::PermissionRequest(request));
+    }
+
+    if tab.zoom.level() != 0.0 {
+        _ = tx.send(TabMessage::ZoomChanged(ZoomChanged {
+            level: tab.zoom.level(),
+            factor: tab.zoom.factor(),
+        }));
+    }
+
     info!("Generated initial state events for tab {}", tab.get_id());
 }
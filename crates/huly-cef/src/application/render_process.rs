@@ -1,14 +1,23 @@
+use std::{cell::RefCell, collections::HashMap};
+
 use anyhow::Result;
 
 use crate::{
-    GET_CLICKABLE_ELEMENTS, GET_ELEMENT_CENTER, IS_ELEMENT_CLICKED, IS_ELEMENT_VISIBLE,
-    IS_INTERACTIVE_ELEMENT, WALK_DOM,
+    CLIPBOARD_BRIDGE, CLIPBOARD_MESSAGE, CTRL_WHEEL_ZOOM, GET_CLICKABLE_ELEMENTS,
+    GET_ELEMENT_CENTER, IS_ELEMENT_CLICKED, IS_ELEMENT_VISIBLE, IS_INTERACTIVE_ELEMENT,
+    SET_CLIPBOARD_MESSAGE, WALK_DOM,
 };
 use cef_ui::{
     register_extension, Browser, Frame, ProcessId, ProcessMessage, RenderProcessHandlerCallbacks,
     V8Context, V8Handler, V8HandlerCallbacks, V8Value,
 };
 
+thread_local! {
+    /// The clipboard bridge of the main frame of each browser, see [`CLIPBOARD_BRIDGE`].
+    static CLIPBOARD_SETTERS: RefCell<HashMap<i32, (V8Context, V8Value)>> =
+        RefCell::new(HashMap::new());
+}
+
 pub struct RenderProcessCallbacks;
 
 impl RenderProcessHandlerCallbacks for RenderProcessCallbacks {
@@ -37,6 +46,100 @@ impl RenderProcessHandlerCallbacks for RenderProcessCallbacks {
             .expect("failed to get global context object")
             .set_value_by_key("sendMessage", func)
             .expect("failed to set sendMessage function");
+
+        install_clipboard_bridge(&browser, &context);
+        _ = frame.execute_java_script(CTRL_WHEEL_ZOOM, "", 0);
+    }
+
+    fn on_context_released(&mut self, browser: Browser, frame: Frame, _: V8Context) {
+        if frame.is_main().unwrap_or_default() {
+            let id = browser.get_identifier().unwrap_or_default();
+            CLIPBOARD_SETTERS.with(|setters| setters.borrow_mut().remove(&id));
+        }
+    }
+
+    fn on_process_message_received(
+        &mut self,
+        browser: Browser,
+        _: Frame,
+        _: ProcessId,
+        message: ProcessMessage,
+    ) -> bool {
+        if message.get_name().unwrap_or_default() != SET_CLIPBOARD_MESSAGE {
+            return false;
+        }
+
+        let clipboard = message
+            .get_argument_list()
+            .ok()
+            .flatten()
+            .and_then(|args| args.get_string(0).ok().flatten())
+            .unwrap_or_default();
+        let id = browser.get_identifier().unwrap_or_default();
+        CLIPBOARD_SETTERS.with(|setters| {
+            if let Some((context, setter)) = setters.borrow().get(&id) {
+                if context.enter().is_ok() {
+                    if let Ok(clipboard) = V8Value::create_string(&clipboard) {
+                        _ = setter.execute_function(None, vec![clipboard]);
+                    }
+                    _ = context.exit();
+                }
+            }
+        });
+        true
+    }
+}
+
+/// Runs the clipboard bridge with the native function it sends copies with. Neither
+/// the native function nor the returned setter is exposed to the page.
+fn install_clipboard_bridge(browser: &Browser, context: &V8Context) {
+    let Ok(bridge) = context.eval(CLIPBOARD_BRIDGE, "", 0) else {
+        return;
+    };
+    let Ok(send_clipboard) = V8Value::create_function(
+        "sendClipboard",
+        V8Handler::new(SendClipboardHandler::new(browser.clone())),
+    ) else {
+        return;
+    };
+
+    if let Ok(setter) = bridge.execute_function(None, vec![send_clipboard]) {
+        let id = browser.get_identifier().unwrap_or_default();
+        CLIPBOARD_SETTERS.with(|setters| {
+            setters.borrow_mut().insert(id, (context.clone(), setter));
+        });
+    }
+}
+
+/// Forwards the content copied by the page, see [`CLIPBOARD_BRIDGE`].
+pub struct SendClipboardHandler {
+    browser: Browser,
+}
+
+impl SendClipboardHandler {
+    pub fn new(browser: Browser) -> Self {
+        Self { browser }
+    }
+}
+
+impl V8HandlerCallbacks for SendClipboardHandler {
+    fn execute(&mut self, _: String, _: V8Value, _: usize, arguments: Vec<V8Value>) -> Result<i32> {
+        let Some(clipboard) = arguments
+            .first()
+            .and_then(|argument| argument.get_string_value().ok())
+        else {
+            return Ok(0);
+        };
+
+        let ipc_message = ProcessMessage::new(CLIPBOARD_MESSAGE);
+        if let Some(argument_list) = ipc_message.get_argument_list().ok().flatten() {
+            _ = argument_list.set_string(0, &clipboard);
+        }
+        if let Some(frame) = self.browser.get_main_frame().ok().flatten() {
+            _ = frame.send_process_message(ProcessId::Browser, ipc_message);
+        }
+
+        Ok(1)
     }
 }
 
@@ -1,5 +1,6 @@
 use std::{
     io::Cursor,
+    path::Path,
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -7,17 +8,26 @@ use std::{
 use anyhow::Result;
 
 use base64::{prelude::BASE64_STANDARD, Engine};
-use cef_ui::{Browser, StringVisitor, StringVisitorCallbacks};
-use image::{imageops::FilterType, ImageFormat};
+use cef_ui::{Browser, PaintElementType, StringVisitor, StringVisitorCallbacks};
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops::{self, FilterType},
+    DynamicImage, RgbaImage,
+};
 use log::{debug, error, info};
+use serde_json::json;
 use tokio::sync::{oneshot, Notify};
 
 use crate::{
-    browser::{devtools::DevTools, mouse::Mouse},
+    browser::{client::download_callbacks::get_file_path, devtools::DevTools, mouse::Mouse},
     state::SharedBrowserState,
-    ClickableElement, LoadState, LoadStatus, MouseButton, TabMessage, TabMessageType,
+    ClickableElement, LoadState, LoadStatus, MouseButton, PdfOptions, ScreenshotClip,
+    ScreenshotFit, ScreenshotFormat, ScreenshotOptions, TabMessage, TabMessageType,
 };
 
+const DEFAULT_SCREENSHOT_QUALITY: u8 = 80;
+const FORCED_PAINT_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub struct DOMVisitor {
     tx: Option<oneshot::Sender<String>>,
 }
@@ -61,9 +71,12 @@ impl Clone for Automation {
 }
 
 impl Automation {
-    pub fn new(browser: Browser, state: SharedBrowserState, mouse: Mouse) -> Self {
-        let devtools = Arc::new(DevTools::new(browser.clone()));
-
+    pub fn new(
+        browser: Browser,
+        state: SharedBrowserState,
+        mouse: Mouse,
+        devtools: Arc<DevTools>,
+    ) -> Self {
         let notify = Arc::new(Notify::new());
         let notify_clone = notify.clone();
 
@@ -109,16 +122,195 @@ impl Automation {
         rx.await.unwrap()
     }
 
-    pub async fn screenshot(&self, width: u32, height: u32) -> Result<String> {
-        let screenshot = self.devtools.screenshot().await?;
-        let screenshot = BASE64_STANDARD.decode(screenshot)?;
-        let screenshot = image::load_from_memory(&screenshot)?;
-        let screenshot = screenshot.resize_exact(width, height, FilterType::Lanczos3);
+    /// Takes a base64 encoded screenshot. Only the `exact` fit needs the image to be
+    /// decoded and resized here, other modes are encoded by the browser directly.
+    pub async fn screenshot(&self, options: &ScreenshotOptions) -> Result<String> {
+        if options.fast {
+            return self.capture_framebuffer(options).await;
+        }
+
+        let mut clip = options.clip;
+        if clip.is_none() && options.full_page {
+            clip = Some(self.devtools.get_layout_metrics().await?.css_content_size);
+        }
+
+        let Some((width, height)) = options.width.zip(options.height) else {
+            return self.capture(options, clip, 1.0, options.format).await;
+        };
+
+        match options.fit {
+            ScreenshotFit::None => self.capture(options, clip, 1.0, options.format).await,
+            ScreenshotFit::Contain => {
+                let clip = match clip {
+                    Some(clip) => clip,
+                    None => {
+                        let viewport = self
+                            .devtools
+                            .get_layout_metrics()
+                            .await?
+                            .css_layout_viewport;
+                        ScreenshotClip {
+                            x: viewport.page_x,
+                            y: viewport.page_y,
+                            width: viewport.client_width,
+                            height: viewport.client_height,
+                        }
+                    }
+                };
+
+                let scale = (width as f64 / clip.width).min(height as f64 / clip.height);
+                self.capture(options, Some(clip), scale, options.format)
+                    .await
+            }
+            ScreenshotFit::Exact => {
+                let screenshot = self
+                    .capture(options, clip, 1.0, ScreenshotFormat::Png)
+                    .await?;
+                let screenshot = BASE64_STANDARD.decode(screenshot)?;
+                let screenshot = image::load_from_memory(&screenshot)?;
+                let screenshot = screenshot.resize_exact(width, height, FilterType::Lanczos3);
+                encode_screenshot(&screenshot, options)
+            }
+        }
+    }
+
+    /// Encodes the tab's framebuffer directly, skipping the DevTools round trip.
+    /// A hidden tab is briefly shown to get a fresh paint.
+    pub async fn capture_framebuffer(&self, options: &ScreenshotOptions) -> Result<String> {
+        if options.full_page {
+            return Err(anyhow::anyhow!(
+                "full page capture is not supported for the framebuffer"
+            ));
+        }
 
-        let mut cursor = Cursor::new(Vec::new());
-        screenshot.write_to(&mut cursor, ImageFormat::Png)?;
+        self.force_paint().await?;
+
+        let (framebuffer, popup) = self
+            .state
+            .read(|s| (s.framebuffer.clone(), s.popup.clone()));
+        let (mut image, dpr) = {
+            let framebuffer = framebuffer.lock().unwrap();
+            let image = RgbaImage::from_raw(
+                framebuffer.width,
+                framebuffer.height,
+                framebuffer.data.clone(),
+            )
+            .ok_or_else(|| anyhow::anyhow!("framebuffer size mismatch"))?;
+            (image, framebuffer.dpr)
+        };
+
+        if let Some(popup) = popup {
+            if let Some(layer) =
+                RgbaImage::from_raw(popup.rect.width, popup.rect.height, popup.data.to_vec())
+            {
+                imageops::replace(&mut image, &layer, popup.rect.x as i64, popup.rect.y as i64);
+            }
+        }
+        let screenshot = DynamicImage::ImageRgba8(image);
+
+        let screenshot = match options.clip {
+            Some(clip) => screenshot.crop_imm(
+                (clip.x * dpr) as u32,
+                (clip.y * dpr) as u32,
+                (clip.width * dpr) as u32,
+                (clip.height * dpr) as u32,
+            ),
+            None => screenshot,
+        };
+
+        let screenshot = match (options.width.zip(options.height), options.fit) {
+            (Some((width, height)), ScreenshotFit::Exact) => {
+                screenshot.resize_exact(width, height, FilterType::Triangle)
+            }
+            (Some((width, height)), ScreenshotFit::Contain) => {
+                screenshot.resize(width, height, FilterType::Triangle)
+            }
+            _ => screenshot,
+        };
 
-        Ok(BASE64_STANDARD.encode(cursor.into_inner()))
+        encode_screenshot(&screenshot, options)
+    }
+
+    async fn force_paint(&self) -> Result<()> {
+        let (active, paint_count) = self.state.update_and_return(|s| {
+            s.capture_requests += 1;
+            (s.active, s.paint_count)
+        });
+
+        let host = self.browser.get_host().unwrap();
+        let painted = if active {
+            Ok(())
+        } else {
+            _ = host.was_hidden(false);
+            _ = host.invalidate(PaintElementType::View);
+            self.state
+                .wait_for(|s| s.paint_count > paint_count, FORCED_PAINT_TIMEOUT)
+                .await
+        };
+
+        let hide = self.state.update_and_return(|s| {
+            s.capture_requests -= 1;
+            !s.active && s.capture_requests == 0
+        });
+        if hide {
+            _ = host.was_hidden(true);
+        }
+
+        painted.map_err(|_| anyhow::anyhow!("timed out waiting for the tab to paint"))
+    }
+
+    /// Renders the page as a base64 encoded PDF.
+    pub async fn print_to_pdf(&self, options: &PdfOptions) -> Result<String> {
+        self.devtools
+            .print_to_pdf(serde_json::to_value(options)?)
+            .await
+    }
+
+    /// Prints the page to a PDF in `dir` and returns its path. `file_name` always
+    /// gets the `.pdf` extension.
+    pub async fn save_pdf(
+        &self,
+        options: &PdfOptions,
+        dir: &Path,
+        file_name: &str,
+    ) -> Result<String> {
+        let file_name = Path::new(file_name).with_extension("pdf");
+        let pdf = BASE64_STANDARD.decode(self.print_to_pdf(options).await?)?;
+        tokio::fs::create_dir_all(dir).await?;
+        let path = get_file_path(dir, &file_name.to_string_lossy())?;
+        tokio::fs::write(&path, pdf).await?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    async fn capture(
+        &self,
+        options: &ScreenshotOptions,
+        clip: Option<ScreenshotClip>,
+        scale: f64,
+        format: ScreenshotFormat,
+    ) -> Result<String> {
+        let mut params = json!({
+            "format": format.as_str(),
+            "captureBeyondViewport": options.full_page,
+        });
+
+        if format != ScreenshotFormat::Png {
+            if let Some(quality) = options.quality {
+                params["quality"] = json!(quality.min(100));
+            }
+        }
+
+        if let Some(clip) = clip {
+            params["clip"] = json!({
+                "x": clip.x,
+                "y": clip.y,
+                "width": clip.width,
+                "height": clip.height,
+                "scale": scale,
+            });
+        }
+
+        self.devtools.screenshot(params).await
     }
 
     pub async fn wait_until_loaded(&mut self) -> Result<(), String> {
@@ -182,9 +374,9 @@ impl Automation {
         let script = format!("getElementCenter('{selector}');");
         let (x, y) = self.execute_javascript::<(i32, i32)>(&script).await;
 
-        self.mouse.click(x, y, MouseButton::Left, true);
+        self.mouse.click(x, y, MouseButton::Left, true, None);
         std::thread::sleep(std::time::Duration::from_millis(20));
-        self.mouse.click(x, y, MouseButton::Left, false);
+        self.mouse.click(x, y, MouseButton::Left, false, None);
         std::thread::sleep(std::time::Duration::from_millis(1000));
 
         info!("Element with id {} clicked", id);
@@ -193,9 +385,9 @@ impl Automation {
         let clicked = self.execute_javascript::<bool>(&script).await;
 
         if !clicked {
-            self.mouse.click(x, y, MouseButton::Left, true);
+            self.mouse.click(x, y, MouseButton::Left, true, None);
             std::thread::sleep(std::time::Duration::from_millis(20));
-            self.mouse.click(x, y, MouseButton::Left, false);
+            self.mouse.click(x, y, MouseButton::Left, false, None);
         }
     }
 
@@ -230,3 +422,22 @@ impl Automation {
         serde_json::from_str::<T>(&response).expect("failed to deserialize a JS response")
     }
 }
+
+fn encode_screenshot(screenshot: &DynamicImage, options: &ScreenshotOptions) -> Result<String> {
+    let mut cursor = Cursor::new(Vec::new());
+    match options.format {
+        ScreenshotFormat::Png => screenshot.write_with_encoder(PngEncoder::new(&mut cursor))?,
+        ScreenshotFormat::Jpeg => {
+            let quality = options.quality.unwrap_or(DEFAULT_SCREENSHOT_QUALITY);
+            screenshot.write_with_encoder(JpegEncoder::new_with_quality(
+                &mut cursor,
+                quality.clamp(1, 100),
+            ))?
+        }
+        ScreenshotFormat::Webp => {
+            screenshot.write_with_encoder(WebPEncoder::new_lossless(&mut cursor))?
+        }
+    }
+
+    Ok(BASE64_STANDARD.encode(cursor.into_inner()))
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use cef_ui::{AudioHandlerCallbacks, AudioParameters, Browser};
+use log::error;
+
+use crate::{browser::state::SharedBrowserState, AudioPacket, AudioStream, TabMessage};
+
+/// Captures the audio of a tab instead of playing it on the server, so that it can
+/// be streamed to clients.
+pub struct HulyAudioHandlerCallbacks {
+    state: SharedBrowserState,
+    sample_rate: u32,
+}
+
+impl HulyAudioHandlerCallbacks {
+    pub fn new(state: SharedBrowserState) -> Self {
+        Self {
+            state,
+            sample_rate: 0,
+        }
+    }
+
+    fn stop(&self) {
+        self.state.update(|s| {
+            s.audio = None;
+            s.audible = false;
+        });
+        self.state.notify(TabMessage::AudioStopped);
+        self.state.notify(TabMessage::Audible(false));
+    }
+}
+
+impl AudioHandlerCallbacks for HulyAudioHandlerCallbacks {
+    fn get_audio_parameters(&mut self, _: Browser, _: &mut AudioParameters) -> bool {
+        // Keep the default parameters, returning false would cancel the stream.
+        true
+    }
+
+    fn on_audio_stream_started(&mut self, _: Browser, params: &AudioParameters, channels: i32) {
+        let stream = AudioStream {
+            sample_rate: params.sample_rate,
+            channels,
+            channel_layout: format!("{:?}", params.channel_layout),
+            frames_per_buffer: params.frames_per_buffer,
+        };
+        self.sample_rate = params.sample_rate as u32;
+        self.state.update(|s| {
+            s.audio = Some(stream.clone());
+            s.audible = true;
+        });
+        self.state.notify(TabMessage::AudioStarted(stream));
+        self.state.notify(TabMessage::Audible(true));
+    }
+
+    fn on_audio_stream_packet(&mut self, _: Browser, data: &[&[f32]], frames: i32, pts: i64) {
+        let frames = frames.max(0) as usize;
+        let mut samples = Vec::with_capacity(frames * data.len());
+        for frame in 0..frames {
+            samples.extend(
+                data.iter()
+                    .map(|channel| channel.get(frame).copied().unwrap_or(0.0)),
+            );
+        }
+
+        self.state.notify(TabMessage::AudioPacket(AudioPacket {
+            data: Arc::new(samples),
+            frames: frames as u32,
+            channels: data.len() as u8,
+            sample_rate: self.sample_rate,
+            pts,
+        }));
+    }
+
+    fn on_audio_stream_stopped(&mut self, _: Browser) {
+        self.stop();
+    }
+
+    fn on_audio_stream_error(&mut self, _: Browser, message: &str) {
+        error!("audio stream error: {}", message);
+        self.stop();
+    }
+}
@@ -2,14 +2,28 @@ use cef_ui::{
     Browser, CursorHandle, CursorInfo, CursorType, DisplayHandlerCallbacks, Frame, LogSeverity,
     Size,
 };
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder};
 use log::error;
 use url::Url;
 
-use crate::{browser::state::SharedBrowserState, messages::TabMessage};
+use crate::{
+    browser::{preferences, state::SharedBrowserState, zoom},
+    messages::{CustomCursor, TabMessage},
+};
+
+const MAX_CACHED_CURSORS: usize = 64;
 
 pub struct HulyDisplayHandlerCallbacks {
     state: SharedBrowserState,
     hovered_url: Option<Url>,
+    cursor_images: HashMap<u64, Arc<String>>,
 }
 
 impl HulyDisplayHandlerCallbacks {
@@ -17,16 +31,76 @@ impl HulyDisplayHandlerCallbacks {
         Self {
             state,
             hovered_url: None,
+            cursor_images: HashMap::new(),
         }
     }
+
+    fn custom_cursor(&mut self, info: &CursorInfo) -> Option<CustomCursor> {
+        let (width, height) = (info.size.width as u32, info.size.height as u32);
+        if info.buffer.len() != width as usize * height as usize * 4 {
+            return None;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        (width, height).hash(&mut hasher);
+        info.buffer.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let image = match self.cursor_images.get(&hash) {
+            Some(image) => image.clone(),
+            None => {
+                let rgba = info
+                    .buffer
+                    .chunks_exact(4)
+                    .flat_map(|p| [p[2], p[1], p[0], p[3]])
+                    .collect::<Vec<u8>>();
+
+                let mut png = Vec::new();
+                if let Err(e) = PngEncoder::new(&mut png).write_image(
+                    &rgba,
+                    width,
+                    height,
+                    ExtendedColorType::Rgba8,
+                ) {
+                    error!("failed to encode a cursor image: {}", e);
+                    return None;
+                }
+
+                if self.cursor_images.len() >= MAX_CACHED_CURSORS {
+                    self.cursor_images.clear();
+                }
+                let image = Arc::new(BASE64_STANDARD.encode(png));
+                self.cursor_images.insert(hash, image.clone());
+                image
+            }
+        };
+
+        Some(CustomCursor {
+            hash: format!("{:016x}", hash),
+            image: Some(image),
+            hotspot_x: info.hotspot.x,
+            hotspot_y: info.hotspot.y,
+            scale: info.image_scale_factor,
+        })
+    }
 }
 
 impl DisplayHandlerCallbacks for HulyDisplayHandlerCallbacks {
-    fn on_address_change(&mut self, _: Browser, _: Frame, url: &str) {
+    fn on_address_change(&mut self, browser: Browser, frame: Frame, url: &str) {
+        let main = frame.is_main().unwrap_or_default();
         self.state.update(|state| {
+            // The clipboard set by the client or copied on one site must not be
+            // pasted into another one.
+            if main && preferences::origin(&state.url) != preferences::origin(url) {
+                state.clipboard = None;
+            }
             state.url = url.to_string();
         });
         self.state.notify(TabMessage::Url(url.to_string()));
+
+        if main {
+            zoom::restore_level(&browser, &self.state, url);
+        }
     }
 
     fn on_title_change(&mut self, _: Browser, title: Option<String>) {
@@ -95,13 +169,22 @@ impl DisplayHandlerCallbacks for HulyDisplayHandlerCallbacks {
         _: Browser,
         _: CursorHandle,
         cursor_type: CursorType,
-        _: Option<CursorInfo>,
+        info: Option<CursorInfo>,
     ) -> bool {
+        let custom_cursor = match (&cursor_type, info) {
+            (CursorType::Custom, Some(info)) => self.custom_cursor(&info),
+            _ => None,
+        };
+
         self.state.update(|state| {
             state.cursor = format!("{:?}", cursor_type);
+            state.custom_cursor = custom_cursor.clone();
         });
         self.state
             .notify(TabMessage::Cursor(format!("{:?}", cursor_type)));
+        if let Some(custom_cursor) = custom_cursor {
+            self.state.notify(TabMessage::CustomCursor(custom_cursor));
+        }
         true
     }
 
@@ -1,5 +1,9 @@
+use std::path::{Path, PathBuf};
+
 use crate::{state::SharedBrowserState, TabMessage};
+use anyhow::{anyhow, Result};
 use cef_ui::{Browser, DownloadHandlerCallbacks, DownloadItem, DownloadItemCallback};
+use log::error;
 
 pub struct MyDownloadHandlerCallbacks {
     state: SharedBrowserState,
@@ -18,11 +22,19 @@ impl DownloadHandlerCallbacks for MyDownloadHandlerCallbacks {
         suggested_name: &str,
         callback: cef_ui::BeforeDownloadCallback,
     ) -> bool {
-        let full_path = get_file_path(suggested_name);
-        callback
-            .continue_download(Some(&full_path), false)
-            .expect("failed to continue download");
-        true
+        let full_path = dirs::download_dir()
+            .ok_or_else(|| anyhow!("no download directory"))
+            .and_then(|dir| get_file_path(&dir, suggested_name));
+        match full_path {
+            Ok(full_path) => {
+                _ = callback.continue_download(Some(&full_path.to_string_lossy()), false);
+                true
+            }
+            Err(e) => {
+                error!("failed to start download of {}: {}", suggested_name, e);
+                false
+            }
+        }
     }
     fn on_download_updated(
         &mut self,
@@ -66,26 +78,75 @@ impl DownloadHandlerCallbacks for MyDownloadHandlerCallbacks {
     }
 }
 
-fn get_file_path(name: &str) -> String {
-    let download_dir = dirs::download_dir().expect("failed to get download dir");
-    let mut full_path = download_dir.join(name);
+/// Returns a path in `dir` for a file named `name` that doesn't exist yet, e.g.
+/// `page (1).pdf`. Only the file name is used, so that the file can't escape `dir`.
+pub(crate) fn get_file_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let name = Path::new(name)
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid file name: {}", name))?;
+    let stem = Path::new(name)
+        .file_stem()
+        .unwrap_or(name)
+        .to_string_lossy()
+        .to_string();
+    let extension = Path::new(name)
+        .extension()
+        .map(|s| format!(".{}", s.to_string_lossy()))
+        .unwrap_or_default();
 
+    let mut full_path = dir.join(name);
     let mut counter = 1;
     while full_path.exists() {
-        let stem = std::path::Path::new(name)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .expect("invalid file name");
-        let extension = std::path::Path::new(name)
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| format!(".{}", s))
-            .expect("invalid file extension");
-
-        let new_name = format!("{} ({}){}", stem, counter, extension);
-        full_path = download_dir.join(new_name);
+        full_path = dir.join(format!("{} ({}){}", stem, counter, extension));
         counter += 1;
     }
 
-    return full_path.to_str().unwrap_or(name).to_string();
+    Ok(full_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn only_the_file_name_is_used() {
+        let dir = temp_dir();
+        assert_eq!(
+            get_file_path(&dir, "../../page.pdf").unwrap(),
+            dir.join("page.pdf")
+        );
+        assert_eq!(
+            get_file_path(&dir, "/etc/page.pdf").unwrap(),
+            dir.join("page.pdf")
+        );
+        assert!(get_file_path(&dir, "..").is_err());
+        assert!(get_file_path(&dir, "").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn existing_files_are_not_overwritten() {
+        let dir = temp_dir();
+        fs::write(dir.join("page.pdf"), "").unwrap();
+        fs::write(dir.join("page (1).pdf"), "").unwrap();
+        fs::write(dir.join("README"), "").unwrap();
+
+        assert_eq!(
+            get_file_path(&dir, "page.pdf").unwrap(),
+            dir.join("page (2).pdf")
+        );
+        assert_eq!(
+            get_file_path(&dir, "README").unwrap(),
+            dir.join("README (1)")
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,38 @@
+use cef_ui::{Browser, FindHandlerCallbacks, Rect};
+
+use crate::{browser::state::SharedBrowserState, FindResult, TabMessage, ViewRect};
+
+pub struct HulyFindHandlerCallbacks {
+    state: SharedBrowserState,
+}
+
+impl HulyFindHandlerCallbacks {
+    pub fn new(state: SharedBrowserState) -> Self {
+        Self { state }
+    }
+}
+
+impl FindHandlerCallbacks for HulyFindHandlerCallbacks {
+    fn on_find_result(
+        &mut self,
+        _: Browser,
+        identifier: i32,
+        count: i32,
+        selection_rect: &Rect,
+        active_match_ordinal: i32,
+        final_update: bool,
+    ) {
+        self.state.notify(TabMessage::FindResult(FindResult {
+            identifier,
+            count,
+            active_match: active_match_ordinal,
+            selection_rect: ViewRect {
+                x: selection_rect.x,
+                y: selection_rect.y,
+                width: selection_rect.width,
+                height: selection_rect.height,
+            },
+            final_update,
+        }));
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use cef_ui::Browser;
+use log::info;
+
+use crate::state::SharedBrowserState;
+
+pub const MIN_FRAME_RATE: i32 = 1;
+pub const MAX_FRAME_RATE: i32 = 60;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lowers the frame rate of a tab while its subscribers drop frames and raises it
+/// back to the requested rate once they keep up again.
+pub struct FrameRateController {
+    target: i32,
+    current: i32,
+    dropped_frames: u64,
+    last_check: Instant,
+}
+
+impl FrameRateController {
+    pub fn new(state: &SharedBrowserState) -> Self {
+        let target = state.read(|s| s.frame_rate);
+        Self {
+            target,
+            current: target,
+            dropped_frames: 0,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn on_paint(&mut self, browser: &Browser, state: &SharedBrowserState) {
+        let (target, adaptive) = state.read(|s| (s.frame_rate, s.adaptive_frame_rate));
+        if target != self.target {
+            self.target = target;
+            self.current = target;
+        }
+
+        if !adaptive || self.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+
+        let dropped_frames = state
+            .subscriber_stats()
+            .values()
+            .map(|stats| stats.dropped_frames)
+            .sum::<u64>();
+        let backed_up = dropped_frames > self.dropped_frames;
+        self.dropped_frames = dropped_frames;
+
+        let rate = if backed_up {
+            (self.current * 2 / 3).max(MIN_FRAME_RATE)
+        } else {
+            (self.current + (self.target / 10).max(1)).min(self.target)
+        };
+
+        if rate != self.current {
+            info!("adapting frame rate: {} -> {} fps", self.current, rate);
+            self.current = rate;
+            if let Ok(host) = browser.get_host() {
+                _ = host.set_windowless_frame_rate(rate);
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use std::sync::Mutex;
 
-use crate::{browser::state::SharedBrowserState, LoadState, LoadStatus, TabMessage};
+use crate::{
+    browser::{send_clipboard, state::SharedBrowserState},
+    LoadState, LoadStatus, TabMessage,
+};
 use cef_ui::{Browser, ErrorCode, Frame, LoadHandlerCallbacks, TransitionType};
 
 pub struct HulyLoadHandlerCallbacks {
@@ -61,6 +64,11 @@ impl LoadHandlerCallbacks for HulyLoadHandlerCallbacks {
                 return;
             }
 
+            // The page has lost the clipboard content set before a same-origin navigation.
+            if let Some(clipboard) = self.state.read(|s| s.clipboard.clone()) {
+                send_clipboard(&frame, Some(&clipboard));
+            }
+
             let mut load_state = self.load_state.lock().unwrap();
             (*load_state).status = LoadStatus::Loaded;
 
@@ -1,33 +1,50 @@
-use crate::browser::state::SharedBrowserState;
+use crate::{
+    browser::{state::SharedBrowserState, zoom},
+    ClipboardData, TabMessage, CLIPBOARD_MESSAGE,
+};
 use cef_ui::{
-    Browser, Client, ClientCallbacks, ContextMenuHandler, DialogHandler, DisplayHandler,
-    DownloadHandler, Frame, LifeSpanHandler, LoadHandler, ProcessId, ProcessMessage, RenderHandler,
-    RequestHandler,
+    AudioHandler, Browser, Client, ClientCallbacks, ContextMenuHandler, DialogHandler,
+    DisplayHandler, DownloadHandler, FindHandler, Frame, LifeSpanHandler, LoadHandler,
+    PermissionHandler, ProcessId, ProcessMessage, RenderHandler, RequestHandler,
 };
+use log::error;
 
+mod audio_callbacks;
 mod context_menu_handler;
 mod dialog_callbacks;
 mod display_callbacks;
-mod download_callbacks;
+pub(crate) mod download_callbacks;
+mod find_callbacks;
+pub mod frame_rate;
 mod life_span_callbacks;
 mod load_callbacks;
+mod permission_callbacks;
 mod render_callbacks;
 mod request_callbacks;
 
+/// Id of the messages sent by [`crate::CTRL_WHEEL_ZOOM`].
+const ZOOM_MESSAGE_ID: &str = "__huly_zoom";
+
 pub struct HulyClientCallbacks {
     state: SharedBrowserState,
+    audio_handler: AudioHandler,
     render_handler: RenderHandler,
     load_handler: LoadHandler,
     dialog_handler: DialogHandler,
     display_handler: DisplayHandler,
     download_handler: DownloadHandler,
+    find_handler: FindHandler,
     life_span_handler: LifeSpanHandler,
+    permission_handler: PermissionHandler,
     request_handler: RequestHandler,
     context_menu_handler: ContextMenuHandler,
 }
 
 impl HulyClientCallbacks {
     pub fn new(state: SharedBrowserState) -> Self {
+        let audio_handler = AudioHandler::new(audio_callbacks::HulyAudioHandlerCallbacks::new(
+            state.clone(),
+        ));
         let render_handler = RenderHandler::new(render_callbacks::HulyRenderHandlerCallbacks::new(
             state.clone(),
         ));
@@ -39,6 +56,9 @@ impl HulyClientCallbacks {
         let life_span_handler = LifeSpanHandler::new(
             life_span_callbacks::HulyLifeSpanHandlerCallbacks::new(state.clone()),
         );
+        let permission_handler = PermissionHandler::new(
+            permission_callbacks::HulyPermissionHandlerCallbacks::new(state.clone()),
+        );
         let request_handler = RequestHandler::new(
             request_callbacks::HulyRequestHandlerCallbacks::new(state.clone()),
         );
@@ -47,18 +67,23 @@ impl HulyClientCallbacks {
         let download_handler = DownloadHandler::new(
             download_callbacks::MyDownloadHandlerCallbacks::new(state.clone()),
         );
+        let find_handler =
+            FindHandler::new(find_callbacks::HulyFindHandlerCallbacks::new(state.clone()));
         let dialog_handler = DialogHandler::new(dialog_callbacks::HulyDialogHandlerCallbacks::new(
             state.clone(),
         ));
 
         Self {
             state,
+            audio_handler,
             render_handler,
             load_handler,
             dialog_handler,
             display_handler,
             download_handler,
+            find_handler,
             life_span_handler,
+            permission_handler,
             request_handler,
             context_menu_handler,
         }
@@ -66,6 +91,10 @@ impl HulyClientCallbacks {
 }
 
 impl ClientCallbacks for HulyClientCallbacks {
+    fn get_audio_handler(&mut self) -> Option<AudioHandler> {
+        Some(self.audio_handler.clone())
+    }
+
     fn get_context_menu_handler(&mut self) -> Option<ContextMenuHandler> {
         Some(self.context_menu_handler.clone())
     }
@@ -90,22 +119,47 @@ impl ClientCallbacks for HulyClientCallbacks {
         Some(self.download_handler.clone())
     }
 
+    fn get_find_handler(&mut self) -> Option<FindHandler> {
+        Some(self.find_handler.clone())
+    }
+
     fn get_dialog_handler(&mut self) -> Option<DialogHandler> {
         Some(self.dialog_handler.clone())
     }
 
+    fn get_permission_handler(&mut self) -> Option<PermissionHandler> {
+        Some(self.permission_handler.clone())
+    }
+
     fn get_request_handler(&mut self) -> Option<RequestHandler> {
         Some(self.request_handler.clone())
     }
 
     fn on_process_message_received(
         &mut self,
-        _: Browser,
+        browser: Browser,
         _: Frame,
         _: ProcessId,
         ipc_msg: ProcessMessage,
     ) -> bool {
         let name = ipc_msg.get_name().unwrap_or_default();
+        if name == CLIPBOARD_MESSAGE {
+            let msg = ipc_msg
+                .get_argument_list()
+                .ok()
+                .flatten()
+                .and_then(|args| args.get_string(0).ok().flatten())
+                .unwrap_or_default();
+            match serde_json::from_str::<ClipboardData>(&msg) {
+                Ok(clipboard) => {
+                    self.state.update(|s| s.clipboard = Some(clipboard.clone()));
+                    self.state.notify(TabMessage::Clipboard(clipboard));
+                }
+                Err(e) => error!("invalid clipboard message: {}", e),
+            }
+            return true;
+        }
+
         if name == "javascript_message" {
             let args = ipc_msg
                 .get_argument_list()
@@ -115,6 +169,15 @@ impl ClientCallbacks for HulyClientCallbacks {
             let id = args.get_string(0).ok().flatten().expect("no id");
             let msg = args.get_string(1).ok().flatten().expect("no message");
 
+            if id == ZOOM_MESSAGE_ID {
+                match msg.as_str() {
+                    "in" => zoom::zoom_in(&browser, &self.state),
+                    "out" => zoom::zoom_out(&browser, &self.state),
+                    _ => error!("invalid zoom message: {}", msg),
+                }
+                return true;
+            }
+
             self.state.update(|s| {
                 s.js_messages.remove(&id).and_then(|tx| Some(tx.send(msg)));
             });
@@ -0,0 +1,61 @@
+use cef_ui::{
+    Browser, Frame, MediaAccessCallback, PermissionHandlerCallbacks, PermissionPromptCallback,
+    PermissionRequestResult,
+};
+
+use crate::browser::{permissions, state::SharedBrowserState};
+
+/// Forwards permission requests to the clients of the tab.
+pub struct HulyPermissionHandlerCallbacks {
+    state: SharedBrowserState,
+}
+
+impl HulyPermissionHandlerCallbacks {
+    pub fn new(state: SharedBrowserState) -> Self {
+        Self { state }
+    }
+}
+
+impl PermissionHandlerCallbacks for HulyPermissionHandlerCallbacks {
+    fn on_request_media_access_permission(
+        &mut self,
+        _: Browser,
+        _: Frame,
+        requesting_origin: &str,
+        requested_permissions: u32,
+        callback: MediaAccessCallback,
+    ) -> bool {
+        permissions::request_media_access(
+            &self.state,
+            requesting_origin,
+            requested_permissions,
+            callback,
+        )
+    }
+
+    fn on_show_permission_prompt(
+        &mut self,
+        _: Browser,
+        prompt_id: u64,
+        requesting_origin: &str,
+        requested_permissions: u32,
+        callback: PermissionPromptCallback,
+    ) -> bool {
+        permissions::show_prompt(
+            &self.state,
+            prompt_id,
+            requesting_origin,
+            requested_permissions,
+            callback,
+        )
+    }
+
+    fn on_dismiss_permission_prompt(
+        &mut self,
+        _: Browser,
+        prompt_id: u64,
+        _: PermissionRequestResult,
+    ) {
+        permissions::dismiss_prompt(&self.state, prompt_id);
+    }
+}
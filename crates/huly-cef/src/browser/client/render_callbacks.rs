@@ -1,10 +1,21 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{state::SharedBrowserState, Framebuffer, TabMessage};
-use cef_ui::{Browser, PaintElementType, Rect, RenderHandlerCallbacks, ScreenInfo};
+use crate::{
+    browser::client::frame_rate::FrameRateController,
+    state::{DragState, SharedBrowserState},
+    DirtyRect, Framebuffer, ImeComposition, PopupLayer, TabMessage, TextRange, ViewRect,
+};
+use cef_ui::{
+    Browser, DragData, DragOperationsMask, MouseEvent, PaintElementType, Range, Rect,
+    RenderHandlerCallbacks, ScreenInfo,
+};
+use log::error;
 
 impl Framebuffer {
-    fn new(width: u32, height: u32, dpr: f64) -> Self {
+    pub(crate) fn new(width: u32, height: u32, dpr: f64) -> Self {
         let dpr_width = width as f64 * dpr;
         let dpr_height = height as f64 * dpr;
         let size = dpr_width * dpr_height * 4.0;
@@ -45,76 +56,128 @@ impl Framebuffer {
         }
     }
 
+    fn copy_from(&mut self, other: &Framebuffer) {
+        self.width = other.width;
+        self.height = other.height;
+        self.dpr = other.dpr;
+        self.data.clone_from(&other.data);
+    }
+
     fn len(&self) -> usize {
         self.data.len()
     }
+
+    fn matches(&self, width: u32, height: u32, dpr: f64) -> bool {
+        self.dpr == dpr
+            && self.width == (width as f64 * dpr) as u32
+            && self.height == (height as f64 * dpr) as u32
+    }
+
+    fn clip(&self, rect: &Rect) -> Option<DirtyRect> {
+        let x = rect.x.clamp(0, self.width as i32) as u32;
+        let y = rect.y.clamp(0, self.height as i32) as u32;
+        let right = (rect.x + rect.width).clamp(0, self.width as i32) as u32;
+        let bottom = (rect.y + rect.height).clamp(0, self.height as i32) as u32;
+
+        if right <= x || bottom <= y {
+            return None;
+        }
+
+        Some(DirtyRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
 }
 
 pub struct HulyRenderHandlerCallbacks {
     state: SharedBrowserState,
 
     framebuffer: Arc<Mutex<Framebuffer>>,
+    /// Copy of the framebuffer written to the shared-memory ring.
+    shared_frame: Framebuffer,
     popup_rect: Option<Rect>,
-    popup_data: Option<Vec<u8>>,
+    frame_rate: FrameRateController,
+    frame_sequence: u64,
+    popup_sequence: u64,
 }
 
 impl HulyRenderHandlerCallbacks {
     pub fn new(state: SharedBrowserState) -> Self {
-        let (w, h, dpr) = state.read(|s| (s.width, s.height, s.dpr));
-        let framebuffer = Arc::new(Mutex::new(Framebuffer::new(w, h, dpr)));
+        let framebuffer = state.read(|s| s.framebuffer.clone());
+        let frame_rate = FrameRateController::new(&state);
 
         Self {
             state,
             framebuffer,
+            shared_frame: Framebuffer::default(),
             popup_rect: None,
-            popup_data: None,
+            frame_rate,
+            frame_sequence: 0,
+            popup_sequence: 0,
         }
     }
 
-    fn draw_view(&mut self, buffer: &[u8], width: usize, dirty_rects: &[Rect]) {
+    fn draw_view(&mut self, buffer: &[u8], width: usize, dirty_rects: &[Rect]) -> Vec<DirtyRect> {
         let mut framebuffer = self.framebuffer.lock().unwrap();
         if framebuffer.len() != buffer.len() {
-            return;
+            return Vec::new();
         }
 
         let src_stride = width * 4;
         for rect in dirty_rects {
             framebuffer.copy_rect(buffer, src_stride, rect, rect);
         }
-    }
 
-    fn draw_popup(&mut self) {
-        let mut framebuffer = self.framebuffer.lock().unwrap();
-
-        let popup_rect = self
-            .popup_rect
-            .as_ref()
-            .expect("popup rect can't be None here");
+        dirty_rects
+            .iter()
+            .filter_map(|rect| framebuffer.clip(rect))
+            .collect()
+    }
 
-        let popup_data = self
-            .popup_data
-            .as_ref()
-            .expect("popup data can't be None here");
+    fn draw_popup(&mut self, buffer: &[u8], width: usize, height: usize) -> Option<PopupLayer> {
+        let popup_rect = self.popup_rect.as_ref()?;
+        if buffer.len() != width * height * 4 {
+            return None;
+        }
 
-        let src_stride = popup_rect.width as usize * 4;
-        let src_rect = &Rect {
-            x: 0,
-            y: 0,
-            width: popup_rect.width,
-            height: popup_rect.height,
+        // Only the part of the popup inside the view is kept, like the dirty rects.
+        let rect = Rect {
+            x: popup_rect.x,
+            y: popup_rect.y,
+            width: width as i32,
+            height: height as i32,
         };
+        let visible = self.framebuffer.lock().unwrap().clip(&rect)?;
+        self.popup_sequence += 1;
+
+        let src_x = (visible.x as i32 - rect.x) as usize;
+        let src_y = (visible.y as i32 - rect.y) as usize;
+        let row_length = visible.width as usize * 4;
+        let mut data = vec![0; row_length * visible.height as usize];
+        for (row, dst) in data.chunks_exact_mut(row_length).enumerate() {
+            let start = ((src_y + row) * width + src_x) * 4;
+            Framebuffer::convert_bgra_to_rgba(dst, &buffer[start..start + row_length]);
+        }
 
-        framebuffer.copy_rect(popup_data, src_stride, src_rect, popup_rect);
+        Some(PopupLayer {
+            rect: visible,
+            data: Arc::new(data),
+            dpr: self.state.read(|s| s.dpr),
+            sequence: self.popup_sequence,
+            timestamp: timestamp(),
+        })
     }
 }
 
 impl RenderHandlerCallbacks for HulyRenderHandlerCallbacks {
     fn get_view_rect(&mut self, _: Browser) -> Rect {
         let (w, h, dpr) = self.state.read(|s| (s.width, s.height, s.dpr));
-        let new_fb_length = Framebuffer::length_in_bytes(w, h, dpr);
 
         let mut framebuffer = self.framebuffer.lock().unwrap();
-        if framebuffer.len() != new_fb_length {
+        if !framebuffer.matches(w, h, dpr) {
             *framebuffer = Framebuffer::new(w, h, dpr);
         }
 
@@ -154,7 +217,8 @@ impl RenderHandlerCallbacks for HulyRenderHandlerCallbacks {
     fn on_popup_show(&mut self, _: Browser, show: bool) {
         if !show {
             self.popup_rect = None;
-            self.popup_data = None;
+            self.state.update(|state| state.popup = None);
+            self.state.notify(TabMessage::PopupHidden);
         }
     }
 
@@ -171,32 +235,134 @@ impl RenderHandlerCallbacks for HulyRenderHandlerCallbacks {
 
     fn on_paint(
         &mut self,
-        _: Browser,
+        browser: Browser,
         paint_element_type: PaintElementType,
         dirty_rects: &[Rect],
         buffer: &[u8],
         width: usize,
-        _height: usize,
+        height: usize,
     ) {
-        let active = self.state.read(|state| state.active);
-        if !active {
+        let (active, capturing) = self
+            .state
+            .read(|state| (state.active, state.capture_requests > 0));
+        if !active && !capturing {
             return;
         }
 
-        match paint_element_type {
-            PaintElementType::View => {
-                self.draw_view(buffer, width, dirty_rects);
-            }
+        let dirty_rects = match paint_element_type {
+            PaintElementType::View => self.draw_view(buffer, width, dirty_rects),
             PaintElementType::Popup => {
-                self.popup_data = Some(buffer.to_vec());
+                let Some(popup) = self.draw_popup(buffer, width, height) else {
+                    return;
+                };
+
+                self.state.update(|state| state.popup = Some(popup.clone()));
+                if active {
+                    self.state.notify(TabMessage::Popup(popup));
+                }
+                return;
             }
+        };
+
+        if dirty_rects.is_empty() {
+            return;
         }
 
-        if self.popup_rect.is_some() && self.popup_data.is_some() {
-            self.draw_popup();
+        self.state.update(|state| state.paint_count += 1);
+        if !active {
+            return;
         }
 
+        let shared_frames = self.state.read(|state| state.shared_frames.clone());
+        if let Some(ring) = shared_frames {
+            // The ring is written after releasing the framebuffer, so that tab sockets
+            // aren't blocked on the file I/O.
+            self.shared_frame
+                .copy_from(&self.framebuffer.lock().unwrap());
+            match ring
+                .lock()
+                .unwrap()
+                .write(&self.shared_frame, dirty_rects.clone())
+            {
+                Ok(frame) => self.state.notify(TabMessage::FrameReady(frame)),
+                Err(e) => error!("failed to write a shared frame: {:?}", e),
+            }
+        }
+
+        self.frame_sequence += 1;
+        self.state.notify(TabMessage::Frame {
+            framebuffer: self.framebuffer.clone(),
+            dirty_rects,
+            sequence: self.frame_sequence,
+            timestamp: timestamp(),
+        });
+        self.frame_rate.on_paint(&browser, &self.state);
+    }
+
+    /// The page started dragging. The drag is kept inside the browser: it follows
+    /// the mouse events and is dropped once the left mouse button is released.
+    fn start_dragging(
+        &mut self,
+        browser: Browser,
+        drag_data: DragData,
+        allowed_ops: DragOperationsMask,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        let Ok(host) = browser.get_host() else {
+            return false;
+        };
+
+        let modifiers = self.state.update_and_return(|state| {
+            state.drag = Some(DragState {
+                allowed_ops,
+                operation: DragOperationsMask::empty(),
+            });
+            state.input.get_event_flags()
+        });
+
+        let event = MouseEvent { x, y, modifiers };
+        _ = host.drag_target_drag_enter(drag_data, &event, allowed_ops);
+        _ = host.drag_target_drag_over(&event, allowed_ops);
+        true
+    }
+
+    fn update_drag_cursor(&mut self, _: Browser, operation: DragOperationsMask) {
+        self.state.update(|state| {
+            if let Some(drag) = state.drag.as_mut() {
+                drag.operation = operation;
+            }
+        });
+    }
+
+    fn on_ime_composition_range_changed(
+        &mut self,
+        _: Browser,
+        selected_range: &Range,
+        character_bounds: &[Rect],
+    ) {
         self.state
-            .notify(TabMessage::Frame(self.framebuffer.clone()));
+            .notify(TabMessage::ImeComposition(ImeComposition {
+                range: TextRange {
+                    from: selected_range.from,
+                    to: selected_range.to,
+                },
+                character_bounds: character_bounds
+                    .iter()
+                    .map(|rect| ViewRect {
+                        x: rect.x,
+                        y: rect.y,
+                        width: rect.width,
+                        height: rect.height,
+                    })
+                    .collect(),
+            }));
     }
 }
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
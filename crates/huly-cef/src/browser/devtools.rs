@@ -5,15 +5,21 @@ use std::sync::atomic::Ordering;
 use std::sync::{atomic::AtomicI32, Arc, Mutex};
 
 use cef_ui::{
-    Browser, DevToolsMessageObserver, DevToolsMessageObserverCallbacks, DictionaryValue,
+    Browser, DevToolsMessageObserver, DevToolsMessageObserverCallbacks, DictionaryValue, ListValue,
     Registration,
 };
 use log::trace;
-use serde::Deserialize;
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
+use serde_json::{Map, Value};
 use tokio::sync::oneshot;
+
+use crate::ScreenshotClip;
+
 #[derive(Debug)]
 struct Response {
-    #[allow(dead_code)]
     success: bool,
     data: Vec<u8>,
 }
@@ -23,6 +29,27 @@ struct Screenshot {
     data: String,
 }
 
+#[derive(Deserialize)]
+struct Pdf {
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutViewport {
+    pub page_x: f64,
+    pub page_y: f64,
+    pub client_width: f64,
+    pub client_height: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutMetrics {
+    pub css_layout_viewport: LayoutViewport,
+    pub css_content_size: ScreenshotClip,
+}
+
 #[derive(Default)]
 struct DevToolsState {
     pending_requests: HashMap<i32, oneshot::Sender<Response>>,
@@ -90,18 +117,47 @@ impl DevTools {
         }
     }
 
-    pub async fn screenshot(&self) -> Result<String> {
-        let response = self.execute_method("Page.captureScreenshot", None).await;
+    /// Captures a screenshot with the given `Page.captureScreenshot` parameters
+    /// and returns it base64 encoded.
+    pub async fn screenshot(&self, params: Value) -> Result<String> {
+        let screenshot = self
+            .call::<Screenshot>("Page.captureScreenshot", params)
+            .await?;
+        Ok(screenshot.data)
+    }
 
+    /// Renders the page as a PDF with the given `Page.printToPDF` parameters
+    /// and returns it base64 encoded.
+    pub async fn print_to_pdf(&self, params: Value) -> Result<String> {
+        let pdf = self.call::<Pdf>("Page.printToPDF", params).await?;
+        Ok(pdf.data)
+    }
+
+    pub async fn get_layout_metrics(&self) -> Result<LayoutMetrics> {
+        self.call("Page.getLayoutMetrics", Value::Null).await
+    }
+
+    /// Runs a method whose result is of no interest, e.g. `Emulation.*` setters.
+    pub async fn execute(&self, name: &str, params: Value) -> Result<()> {
+        self.call::<IgnoredAny>(name, params).await.map(|_| ())
+    }
+
+    async fn call<T: DeserializeOwned>(&self, name: &str, params: Value) -> Result<T> {
+        let params = match params {
+            Value::Object(params) => Some(to_dictionary(&params)),
+            _ => None,
+        };
+
+        let response = self.execute_method(name, params).await;
         if !response.success {
             return Err(anyhow::anyhow!(
-                "Failed to capture screenshot: {}",
+                "{} failed: {}",
+                name,
                 String::from_utf8_lossy(&response.data)
             ));
         }
 
-        let screenshot = serde_json::from_slice::<Screenshot>(&response.data)?;
-        Ok(screenshot.data)
+        Ok(serde_json::from_slice::<T>(&response.data)?)
     }
 
     async fn execute_method(&self, name: &str, params: Option<DictionaryValue>) -> Response {
@@ -119,6 +175,43 @@ impl DevTools {
     }
 }
 
+fn to_dictionary(params: &Map<String, Value>) -> DictionaryValue {
+    let dictionary = DictionaryValue::new();
+    for (key, value) in params {
+        _ = match value {
+            Value::Bool(value) => dictionary.set_bool(key, *value),
+            Value::Number(value) => match value.as_i64().and_then(|v| i32::try_from(v).ok()) {
+                Some(value) => dictionary.set_int(key, value),
+                None => dictionary.set_double(key, value.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => dictionary.set_string(key, value),
+            Value::Object(value) => dictionary.set_dictionary(key, to_dictionary(value)),
+            Value::Array(values) => dictionary.set_list(key, to_list(values)),
+            Value::Null => continue,
+        };
+    }
+    dictionary
+}
+
+fn to_list(values: &[Value]) -> ListValue {
+    let list = ListValue::new();
+    _ = list.set_size(values.len());
+    for (index, value) in values.iter().enumerate() {
+        _ = match value {
+            Value::Bool(value) => list.set_bool(index, *value),
+            Value::Number(value) => match value.as_i64().and_then(|v| i32::try_from(v).ok()) {
+                Some(value) => list.set_int(index, value),
+                None => list.set_double(index, value.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => list.set_string(index, value),
+            Value::Object(value) => list.set_dictionary(index, to_dictionary(value)),
+            Value::Array(values) => list.set_list(index, to_list(values)),
+            Value::Null => list.set_null(index),
+        };
+    }
+    list
+}
+
 struct DevToolsObserverCallbacks {
     state: Arc<SharedDevToolsState>,
 }
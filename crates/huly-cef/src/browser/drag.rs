@@ -0,0 +1,118 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use cef_ui::{Browser, BrowserHost, DragData, DragOperationsMask, MouseEvent};
+use log::error;
+
+use crate::{state::SharedBrowserState, DropData, DroppedFile};
+
+/// Drags data from the client's machine onto the page. Drags started by the page
+/// itself follow the mouse events instead.
+pub struct Drag {
+    inner: Browser,
+    state: SharedBrowserState,
+}
+
+impl Clone for Drag {
+    fn clone(&self) -> Self {
+        Drag {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl Drag {
+    pub fn new(inner: Browser, state: SharedBrowserState) -> Self {
+        Drag { inner, state }
+    }
+
+    /// Starts dragging `data` over the page. Dropped files are written to a temporary
+    /// directory, which is kept until the next drag or until the tab is closed, so that
+    /// the page can still read them after the drop.
+    pub fn enter(&self, x: i32, y: i32, data: DropData) -> Result<()> {
+        let host = self.host().ok_or_else(|| anyhow!("browser host is gone"))?;
+        self.remove_dropped_files();
+
+        let drag_data = DragData::new();
+        if let Some(text) = &data.text {
+            _ = drag_data.set_fragment_text(text);
+        }
+        if let Some(html) = &data.html {
+            _ = drag_data.set_fragment_html(html);
+        }
+        if let Some(url) = &data.url {
+            _ = drag_data.set_link_url(url);
+        }
+        if !data.files.is_empty() {
+            let dir = std::env::temp_dir()
+                .join("huly-cef-drops")
+                .join(uuid::Uuid::new_v4().to_string());
+            fs::create_dir_all(&dir)?;
+            self.state.update(|s| s.dropped_files = Some(dir.clone()));
+
+            for file in &data.files {
+                let path = write_file(&dir, file)?;
+                _ = drag_data.add_file(&path, &file.name);
+            }
+        }
+
+        let event = self.event(x, y);
+        _ = host.drag_target_drag_enter(drag_data, &event, DragOperationsMask::Copy);
+        Ok(())
+    }
+
+    pub fn over(&self, x: i32, y: i32) {
+        let event = self.event(x, y);
+        if let Some(host) = self.host() {
+            _ = host.drag_target_drag_over(&event, DragOperationsMask::Copy);
+        }
+    }
+
+    pub fn leave(&self) {
+        if let Some(host) = self.host() {
+            _ = host.drag_target_drag_leave();
+        }
+        self.remove_dropped_files();
+    }
+
+    pub fn drop_at(&self, x: i32, y: i32) {
+        let event = self.event(x, y);
+        if let Some(host) = self.host() {
+            _ = host.drag_target_drop(&event);
+        }
+    }
+
+    pub(crate) fn remove_dropped_files(&self) {
+        let dir = self.state.update_and_return(|s| s.dropped_files.take());
+        if let Some(dir) = dir {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                error!("failed to remove {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    fn host(&self) -> Option<BrowserHost> {
+        let host = self.inner.get_host().ok();
+        if host.is_none() {
+            error!("failed to drag: browser host is gone");
+        }
+        host
+    }
+
+    fn event(&self, x: i32, y: i32) -> MouseEvent {
+        let modifiers = self.state.read(|s| s.input.get_event_flags());
+        MouseEvent { x, y, modifiers }
+    }
+}
+
+fn write_file(dir: &Path, file: &DroppedFile) -> Result<String> {
+    // Only the file name is used, so that a dropped file can't escape the directory.
+    let name = Path::new(&file.name)
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid file name: {}", file.name))?;
+    let path = dir.join(name);
+    fs::write(&path, BASE64_STANDARD.decode(&file.data)?)?;
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::{
+    browser::devtools::DevTools, state::SharedBrowserState, ColorScheme, EmulationSettings,
+};
+
+const MAX_TOUCH_POINTS: i32 = 5;
+
+/// Emulates other devices through `Emulation.*` DevTools methods. The overrides
+/// belong to the tab's DevTools session, so they stay active across navigations.
+pub struct Emulation {
+    devtools: Arc<DevTools>,
+    state: SharedBrowserState,
+}
+
+impl Clone for Emulation {
+    fn clone(&self) -> Self {
+        Emulation {
+            devtools: self.devtools.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl Emulation {
+    pub fn new(devtools: Arc<DevTools>, state: SharedBrowserState) -> Self {
+        Emulation { devtools, state }
+    }
+
+    pub fn get(&self) -> EmulationSettings {
+        self.state.read(|s| s.emulation.clone())
+    }
+
+    /// Replaces all emulation settings of the tab.
+    pub async fn set(&self, settings: EmulationSettings) -> Result<()> {
+        match settings.viewport {
+            Some(viewport) => {
+                self.devtools
+                    .execute(
+                        "Emulation.setDeviceMetricsOverride",
+                        json!({
+                            "width": viewport.width,
+                            "height": viewport.height,
+                            "deviceScaleFactor": viewport.device_scale_factor,
+                            "mobile": viewport.mobile,
+                        }),
+                    )
+                    .await?
+            }
+            None => {
+                self.devtools
+                    .execute("Emulation.clearDeviceMetricsOverride", json!({}))
+                    .await?
+            }
+        }
+
+        self.devtools
+            .execute(
+                "Emulation.setTouchEmulationEnabled",
+                json!({
+                    "enabled": settings.touch,
+                    "maxTouchPoints": MAX_TOUCH_POINTS,
+                }),
+            )
+            .await?;
+
+        // An empty user agent removes the override.
+        self.devtools
+            .execute(
+                "Emulation.setUserAgentOverride",
+                json!({ "userAgent": settings.user_agent.clone().unwrap_or_default() }),
+            )
+            .await?;
+
+        let color_scheme = match settings.color_scheme {
+            Some(ColorScheme::Light) => "light",
+            Some(ColorScheme::Dark) => "dark",
+            None => "",
+        };
+        let reduced_motion = if settings.reduced_motion {
+            "reduce"
+        } else {
+            ""
+        };
+        self.devtools
+            .execute(
+                "Emulation.setEmulatedMedia",
+                json!({
+                    "media": if settings.print_media { "print" } else { "" },
+                    "features": [
+                        { "name": "prefers-color-scheme", "value": color_scheme },
+                        { "name": "prefers-reduced-motion", "value": reduced_motion },
+                    ],
+                }),
+            )
+            .await?;
+
+        self.state.update(|s| s.emulation = settings);
+        Ok(())
+    }
+}
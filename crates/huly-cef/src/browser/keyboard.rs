@@ -1,6 +1,20 @@
-use cef_ui::{Browser, BrowserHost, EventFlags, KeyEvent, KeyEventType};
+use std::time::Duration;
 
-use crate::state::SharedBrowserState;
+use anyhow::Result;
+use cef_ui::{Browser, BrowserHost, EventFlags, KeyEvent, KeyEventType, Range};
+use log::error;
+
+use crate::{
+    browser::keymap::{self, Chord, Key, Modifier, Modifiers},
+    state::SharedBrowserState,
+    TextRange,
+};
+
+/// Tells CEF to use the current selection or composition.
+const INVALID_RANGE: Range = Range {
+    from: u32::MAX,
+    to: u32::MAX,
+};
 
 pub struct Keyboard {
     inner: Browser,
@@ -81,6 +95,136 @@ impl Keyboard {
             _ = self.inner.get_host().unwrap().send_key_event(event);
         }
     }
+
+    /// Presses and releases a key combination such as `Ctrl+Shift+T`, see [`Chord::parse`].
+    pub fn press(&self, chord: &str) -> Result<()> {
+        let chord = Chord::parse(chord)?;
+
+        let mut modifiers = Modifiers::default();
+        for modifier in &chord.modifiers {
+            modifiers.set(*modifier, true);
+            self.send_key(modifier.key(), 0, true, modifiers);
+        }
+
+        let character = if modifiers.shift {
+            chord.key.shifted_character
+        } else {
+            chord.key.character
+        };
+        self.send_key(chord.key, character, true, modifiers);
+        self.send_key(chord.key, character, false, modifiers);
+
+        for modifier in chord.modifiers.iter().rev() {
+            modifiers.set(*modifier, false);
+            self.send_key(modifier.key(), 0, false, modifiers);
+        }
+        Ok(())
+    }
+
+    /// Types `text` key by key, waiting `delay` after every character. Characters
+    /// that are missing from the US layout, e.g. non-ASCII ones, are committed as IME
+    /// text, so that they are inserted as a whole.
+    pub async fn type_text(&self, text: &str, delay: Duration) {
+        for c in text.chars() {
+            match keymap::char_key(c) {
+                Some((key, shift)) => {
+                    let modifiers = Modifiers {
+                        shift,
+                        ..Default::default()
+                    };
+                    if shift {
+                        self.send_key(Modifier::Shift.key(), 0, true, modifiers);
+                    }
+
+                    let character = if shift {
+                        key.shifted_character
+                    } else {
+                        key.character
+                    };
+                    self.send_key(key, character, true, modifiers);
+                    self.send_key(key, character, false, modifiers);
+
+                    if shift {
+                        self.send_key(Modifier::Shift.key(), 0, false, Modifiers::default());
+                    }
+                }
+                None => self.ime_commit_text(&c.to_string(), None),
+            }
+
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    fn send_key(&self, key: Key, character: u16, down: bool, modifiers: Modifiers) {
+        self.key(
+            character,
+            key.windows_code,
+            key.native_code,
+            down,
+            modifiers.ctrl,
+            modifiers.shift,
+            modifiers.alt,
+            modifiers.meta,
+        );
+    }
+
+    /// Starts or updates an IME composition. `selection` is the cursor position
+    /// within `text`, `replacement` is the range of existing text to be replaced.
+    pub fn ime_set_composition(
+        &self,
+        text: &str,
+        selection: TextRange,
+        replacement: Option<TextRange>,
+    ) {
+        if let Some(host) = self.ime_host() {
+            _ = host.ime_set_composition(
+                text,
+                &[],
+                &to_range(replacement),
+                &Range {
+                    from: selection.from,
+                    to: selection.to,
+                },
+            );
+        }
+    }
+
+    /// Inserts `text`, replacing the composition if there is one.
+    pub fn ime_commit_text(&self, text: &str, replacement: Option<TextRange>) {
+        if let Some(host) = self.ime_host() {
+            _ = host.ime_commit_text(text, &to_range(replacement), 0);
+        }
+    }
+
+    /// Commits the current composition as is.
+    pub fn ime_finish_composing_text(&self, keep_selection: bool) {
+        if let Some(host) = self.ime_host() {
+            _ = host.ime_finish_composing_text(keep_selection);
+        }
+    }
+
+    pub fn ime_cancel_composition(&self) {
+        if let Some(host) = self.ime_host() {
+            _ = host.ime_cancel_composition();
+        }
+    }
+
+    fn ime_host(&self) -> Option<BrowserHost> {
+        let host = self.inner.get_host().ok();
+        if host.is_none() {
+            error!("failed to update the IME composition: browser host is gone");
+        }
+        host
+    }
+}
+
+fn to_range(range: Option<TextRange>) -> Range {
+    range.map_or(INVALID_RANGE, |range| Range {
+        from: range.from,
+        to: range.to,
+    })
 }
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
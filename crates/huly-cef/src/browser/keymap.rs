@@ -0,0 +1,297 @@
+use anyhow::{anyhow, Result};
+
+/// A physical key of the US layout. `windows_code` is the Windows virtual key code,
+/// `native_code` the code of the host platform: the macOS virtual key code on macOS
+/// and the Windows one elsewhere, like the TypeScript client sends them.
+#[derive(Debug, Clone, Copy)]
+pub struct Key {
+    pub windows_code: i32,
+    pub native_code: i32,
+    pub character: u16,
+    pub shifted_character: u16,
+}
+
+impl Key {
+    const fn new(windows_code: i32, mac_code: i32, character: char, shifted: char) -> Self {
+        Key {
+            windows_code,
+            native_code: if cfg!(target_os = "macos") {
+                mac_code
+            } else {
+                windows_code
+            },
+            character: character as u16,
+            shifted_character: shifted as u16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl Modifier {
+    pub fn key(&self) -> Key {
+        match self {
+            Modifier::Ctrl => Key::new(0x11, 0x3b, '\0', '\0'),
+            Modifier::Shift => Key::new(0x10, 0x38, '\0', '\0'),
+            Modifier::Alt => Key::new(0x12, 0x3a, '\0', '\0'),
+            Modifier::Meta => Key::new(0x5b, 0x37, '\0', '\0'),
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Some(Modifier::Ctrl),
+            "shift" => Some(Modifier::Shift),
+            "alt" | "option" => Some(Modifier::Alt),
+            "meta" | "cmd" | "command" | "super" | "win" => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl Modifiers {
+    pub fn set(&mut self, modifier: Modifier, down: bool) {
+        match modifier {
+            Modifier::Ctrl => self.ctrl = down,
+            Modifier::Shift => self.shift = down,
+            Modifier::Alt => self.alt = down,
+            Modifier::Meta => self.meta = down,
+        }
+    }
+}
+
+/// A key combination such as `Ctrl+Shift+T`.
+#[derive(Debug, Clone)]
+pub struct Chord {
+    pub modifiers: Vec<Modifier>,
+    pub key: Key,
+}
+
+impl Chord {
+    /// Parses `+` separated modifiers followed by a key name, e.g. `Enter`, `ArrowLeft`
+    /// or `F5`, or by a single character. The plus key itself is written as `Ctrl++`.
+    pub fn parse(chord: &str) -> Result<Self> {
+        let (modifiers, key) = match chord.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if chord == "+" => ("", chord),
+            None => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+
+        let modifiers = modifiers
+            .split('+')
+            .filter(|name| !name.is_empty())
+            .map(|name| Modifier::parse(name).ok_or_else(|| anyhow!("unknown modifier: {}", name)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let key = named_key(key)
+            .or_else(|| Modifier::parse(key).map(|modifier| modifier.key()))
+            .or_else(|| single_char(key).and_then(char_key).map(|(key, _)| key))
+            .ok_or_else(|| anyhow!("unknown key: {}", key))?;
+
+        Ok(Chord { modifiers, key })
+    }
+}
+
+/// Returns the key producing `c` and whether Shift has to be held for it.
+pub fn char_key(c: char) -> Option<(Key, bool)> {
+    match c {
+        '\n' | '\r' => return named_key("Enter").map(|key| (key, false)),
+        '\t' => return named_key("Tab").map(|key| (key, false)),
+        ' ' => return named_key("Space").map(|key| (key, false)),
+        _ => {}
+    }
+
+    PRINTABLE_KEYS.iter().find_map(|key| {
+        if key.character == c as u16 {
+            Some((*key, false))
+        } else if key.shifted_character == c as u16 {
+            Some((*key, true))
+        } else {
+            None
+        }
+    })
+}
+
+fn named_key(name: &str) -> Option<Key> {
+    let name = name.to_lowercase();
+    let name = match name.as_str() {
+        "return" => "enter",
+        "esc" => "escape",
+        "del" => "delete",
+        "left" => "arrowleft",
+        "up" => "arrowup",
+        "right" => "arrowright",
+        "down" => "arrowdown",
+        name => name,
+    };
+
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Backspace", Key::new(0x08, 0x33, '\u{8}', '\u{8}')),
+    ("Tab", Key::new(0x09, 0x30, '\t', '\t')),
+    ("Enter", Key::new(0x0d, 0x24, '\r', '\r')),
+    ("CapsLock", Key::new(0x14, 0x39, '\0', '\0')),
+    ("Escape", Key::new(0x1b, 0x35, '\u{1b}', '\u{1b}')),
+    ("Space", Key::new(0x20, 0x31, ' ', ' ')),
+    ("PageUp", Key::new(0x21, 0x74, '\0', '\0')),
+    ("PageDown", Key::new(0x22, 0x79, '\0', '\0')),
+    ("End", Key::new(0x23, 0x77, '\0', '\0')),
+    ("Home", Key::new(0x24, 0x73, '\0', '\0')),
+    ("ArrowLeft", Key::new(0x25, 0x7b, '\0', '\0')),
+    ("ArrowUp", Key::new(0x26, 0x7e, '\0', '\0')),
+    ("ArrowRight", Key::new(0x27, 0x7c, '\0', '\0')),
+    ("ArrowDown", Key::new(0x28, 0x7d, '\0', '\0')),
+    ("Insert", Key::new(0x2d, 0x72, '\0', '\0')),
+    ("Delete", Key::new(0x2e, 0x75, '\u{7f}', '\u{7f}')),
+    ("F1", Key::new(0x70, 0x7a, '\0', '\0')),
+    ("F2", Key::new(0x71, 0x78, '\0', '\0')),
+    ("F3", Key::new(0x72, 0x63, '\0', '\0')),
+    ("F4", Key::new(0x73, 0x76, '\0', '\0')),
+    ("F5", Key::new(0x74, 0x60, '\0', '\0')),
+    ("F6", Key::new(0x75, 0x61, '\0', '\0')),
+    ("F7", Key::new(0x76, 0x62, '\0', '\0')),
+    ("F8", Key::new(0x77, 0x64, '\0', '\0')),
+    ("F9", Key::new(0x78, 0x65, '\0', '\0')),
+    ("F10", Key::new(0x79, 0x6d, '\0', '\0')),
+    ("F11", Key::new(0x7a, 0x67, '\0', '\0')),
+    ("F12", Key::new(0x7b, 0x6f, '\0', '\0')),
+];
+
+const PRINTABLE_KEYS: &[Key] = &[
+    Key::new(0x41, 0x00, 'a', 'A'),
+    Key::new(0x42, 0x0b, 'b', 'B'),
+    Key::new(0x43, 0x08, 'c', 'C'),
+    Key::new(0x44, 0x02, 'd', 'D'),
+    Key::new(0x45, 0x0e, 'e', 'E'),
+    Key::new(0x46, 0x03, 'f', 'F'),
+    Key::new(0x47, 0x05, 'g', 'G'),
+    Key::new(0x48, 0x04, 'h', 'H'),
+    Key::new(0x49, 0x22, 'i', 'I'),
+    Key::new(0x4a, 0x26, 'j', 'J'),
+    Key::new(0x4b, 0x28, 'k', 'K'),
+    Key::new(0x4c, 0x25, 'l', 'L'),
+    Key::new(0x4d, 0x2e, 'm', 'M'),
+    Key::new(0x4e, 0x2d, 'n', 'N'),
+    Key::new(0x4f, 0x1f, 'o', 'O'),
+    Key::new(0x50, 0x23, 'p', 'P'),
+    Key::new(0x51, 0x0c, 'q', 'Q'),
+    Key::new(0x52, 0x0f, 'r', 'R'),
+    Key::new(0x53, 0x01, 's', 'S'),
+    Key::new(0x54, 0x11, 't', 'T'),
+    Key::new(0x55, 0x20, 'u', 'U'),
+    Key::new(0x56, 0x09, 'v', 'V'),
+    Key::new(0x57, 0x0d, 'w', 'W'),
+    Key::new(0x58, 0x07, 'x', 'X'),
+    Key::new(0x59, 0x10, 'y', 'Y'),
+    Key::new(0x5a, 0x06, 'z', 'Z'),
+    Key::new(0x30, 0x1d, '0', ')'),
+    Key::new(0x31, 0x12, '1', '!'),
+    Key::new(0x32, 0x13, '2', '@'),
+    Key::new(0x33, 0x14, '3', '#'),
+    Key::new(0x34, 0x15, '4', '$'),
+    Key::new(0x35, 0x17, '5', '%'),
+    Key::new(0x36, 0x16, '6', '^'),
+    Key::new(0x37, 0x1a, '7', '&'),
+    Key::new(0x38, 0x1c, '8', '*'),
+    Key::new(0x39, 0x19, '9', '('),
+    Key::new(0xba, 0x29, ';', ':'),
+    Key::new(0xbb, 0x18, '=', '+'),
+    Key::new(0xbc, 0x2b, ',', '<'),
+    Key::new(0xbd, 0x1b, '-', '_'),
+    Key::new(0xbe, 0x2f, '.', '>'),
+    Key::new(0xbf, 0x2c, '/', '?'),
+    Key::new(0xc0, 0x32, '`', '~'),
+    Key::new(0xdb, 0x21, '[', '{'),
+    Key::new(0xdc, 0x2a, '\\', '|'),
+    Key::new(0xdd, 0x1e, ']', '}'),
+    Key::new(0xde, 0x27, '\'', '"'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chord: &str) -> (Vec<Modifier>, i32) {
+        let chord = Chord::parse(chord).unwrap();
+        (chord.modifiers, chord.key.windows_code)
+    }
+
+    #[test]
+    fn named_keys() {
+        assert_eq!(parse("Enter"), (vec![], 0x0d));
+        assert_eq!(parse("return"), (vec![], 0x0d));
+        assert_eq!(parse("Shift+Left"), (vec![Modifier::Shift], 0x25));
+        assert_eq!(parse("F12"), (vec![], 0x7b));
+        assert_eq!(parse("Ctrl+Shift"), (vec![Modifier::Ctrl], 0x10));
+    }
+
+    #[test]
+    fn modifiers_and_characters() {
+        assert_eq!(
+            parse("Ctrl+Shift+T"),
+            (vec![Modifier::Ctrl, Modifier::Shift], 0x54)
+        );
+        assert_eq!(
+            parse("cmd+alt+t"),
+            (vec![Modifier::Meta, Modifier::Alt], 0x54)
+        );
+        assert_eq!(parse("Ctrl+/"), (vec![Modifier::Ctrl], 0xbf));
+    }
+
+    #[test]
+    fn plus_key() {
+        assert_eq!(parse("+"), (vec![], 0xbb));
+        assert_eq!(parse("Ctrl++"), (vec![Modifier::Ctrl], 0xbb));
+        assert_eq!(
+            parse("Ctrl+Shift++"),
+            (vec![Modifier::Ctrl, Modifier::Shift], 0xbb)
+        );
+    }
+
+    #[test]
+    fn invalid_chords() {
+        assert!(Chord::parse("").is_err());
+        assert!(Chord::parse("Hyper+A").is_err());
+        assert!(Chord::parse("Ctrl+Enterr").is_err());
+        assert!(Chord::parse("Ctrl+é").is_err());
+    }
+
+    #[test]
+    fn char_keys() {
+        let key = |c| char_key(c).map(|(key, shift)| (key.windows_code, shift));
+        assert_eq!(key('a'), Some((0x41, false)));
+        assert_eq!(key('A'), Some((0x41, true)));
+        assert_eq!(key('!'), Some((0x31, true)));
+        assert_eq!(key('\''), Some((0xde, false)));
+        assert_eq!(key('\n'), Some((0x0d, false)));
+        assert_eq!(key(' '), Some((0x20, false)));
+        assert_eq!(key('é'), None);
+    }
+}
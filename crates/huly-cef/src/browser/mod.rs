@@ -1,22 +1,43 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use crossbeam_channel::Sender;
 
 use cef_ui::{
-    BrowserHost, BrowserSettings, CefTask, CefTaskCallbacks, PaintElementType, ThreadId, WindowInfo,
+    BrowserHost, BrowserSettings, CefTask, CefTaskCallbacks, PaintElementType, ProcessId,
+    ProcessMessage, ThreadId, WindowInfo,
 };
 
-use log::info;
-use tokio::sync::mpsc::UnboundedSender;
-
-use crate::{browser::state::SharedBrowserState, LoadState, TabMessage};
+use log::{error, info};
+
+pub use crate::browser::client::frame_rate::{MAX_FRAME_RATE, MIN_FRAME_RATE};
+use crate::{
+    browser::{
+        preferences::Preferences,
+        shared_frames::SharedFrameRing,
+        state::SharedBrowserState,
+        subscriber::{SubscriberSender, SubscriberStats},
+    },
+    ClipboardData, EmulationSettings, Framebuffer, LoadState, TabMessage, SET_CLIPBOARD_MESSAGE,
+};
 
 mod automation;
 mod client;
 mod devtools;
+mod drag;
+mod emulation;
 mod keyboard;
+mod keymap;
 mod mouse;
+pub mod permissions;
+pub mod preferences;
+pub mod shared_frames;
 pub(crate) mod state;
+pub mod subscriber;
+mod touch;
+mod zoom;
 
 // TODO: add sub structs:
 // 1. Navigation
@@ -27,7 +48,11 @@ pub struct Browser {
     pub state: state::SharedBrowserState,
     pub mouse: mouse::Mouse,
     pub keyboard: keyboard::Keyboard,
+    pub touch: touch::Touch,
+    pub drag: drag::Drag,
+    pub zoom: zoom::Zoom,
     pub automation: automation::Automation,
+    pub emulation: emulation::Emulation,
     counter: i32,
 }
 
@@ -38,14 +63,25 @@ impl Clone for Browser {
             state: self.state.clone(),
             mouse: self.mouse.clone(),
             keyboard: self.keyboard.clone(),
+            touch: self.touch.clone(),
+            drag: self.drag.clone(),
+            zoom: self.zoom.clone(),
             automation: self.automation.clone(),
+            emulation: self.emulation.clone(),
             counter: self.counter,
         }
     }
 }
 
 impl Browser {
-    pub fn new(width: u32, height: u32, dpr: f64, url: &str) -> Self {
+    pub fn new(
+        width: u32,
+        height: u32,
+        dpr: f64,
+        frame_rate: i32,
+        url: &str,
+        preferences: Arc<Preferences>,
+    ) -> Self {
         let (tx, rx) = crossbeam_channel::bounded(1);
         let result = cef_ui::post_task(
             ThreadId::UI,
@@ -54,7 +90,9 @@ impl Browser {
                 width,
                 height,
                 dpr,
+                frame_rate: frame_rate.clamp(MIN_FRAME_RATE, MAX_FRAME_RATE),
                 url: url.to_string(),
+                preferences,
             }),
         );
 
@@ -109,6 +147,46 @@ impl Browser {
             .invalidate(PaintElementType::View);
     }
 
+    /// Changes the device pixel ratio, e.g. when the viewer moves to a display
+    /// with a different scale factor. The framebuffer is reallocated right away.
+    pub fn set_device_pixel_ratio(&self, dpr: f64) {
+        let (width, height, framebuffer) = self.state.update_and_return(|state| {
+            state.dpr = dpr;
+            (state.width, state.height, state.framebuffer.clone())
+        });
+        *framebuffer.lock().unwrap() = Framebuffer::new(width, height, dpr);
+
+        let host = self.inner.get_host().unwrap();
+        let _ = host.notify_screen_info_changed();
+        let _ = host.was_resized();
+        let _ = host.invalidate(PaintElementType::View);
+    }
+
+    pub fn get_device_pixel_ratio(&self) -> f64 {
+        self.state.read(|state| state.dpr)
+    }
+
+    /// Sets the frame rate of the tab. In adaptive mode the rate is lowered
+    /// automatically while subscribers can't keep up with it.
+    pub fn set_frame_rate(&self, frame_rate: i32, adaptive: bool) {
+        let frame_rate = frame_rate.clamp(MIN_FRAME_RATE, MAX_FRAME_RATE);
+        self.state.update(|state| {
+            state.frame_rate = frame_rate;
+            state.adaptive_frame_rate = adaptive;
+        });
+
+        let _ = self
+            .inner
+            .get_host()
+            .unwrap()
+            .set_windowless_frame_rate(frame_rate);
+    }
+
+    pub fn get_frame_rate(&self) -> (i32, bool) {
+        self.state
+            .read(|state| (state.frame_rate, state.adaptive_frame_rate))
+    }
+
     pub fn go_to(&mut self, url: &str) {
         info!("navigating to URL: {}", url);
         self.start_navigation();
@@ -131,6 +209,8 @@ impl Browser {
     }
 
     pub fn close(&self) {
+        self.drag.remove_dropped_files();
+        permissions::cancel_all(&self.state);
         let _ = self.inner.get_host().unwrap().close_browser(true);
     }
 
@@ -158,7 +238,7 @@ impl Browser {
         self.state.read(|state| state.load_state.clone())
     }
 
-    pub fn subscribe(&mut self, tx: UnboundedSender<TabMessage>) -> i32 {
+    pub fn subscribe(&mut self, tx: SubscriberSender) -> i32 {
         let id = self.counter;
         self.counter += 1;
         self.state.subscribe(id, tx);
@@ -170,6 +250,37 @@ impl Browser {
         self.state.unsubscribe(id);
     }
 
+    /// Starts writing frames into a shared-memory ring for same-host clients
+    /// and returns the path of its file.
+    pub fn enable_shared_frames(&self) -> anyhow::Result<String> {
+        let ring = match self.state.read(|s| s.shared_frames.clone()) {
+            Some(ring) => ring,
+            None => Arc::new(Mutex::new(SharedFrameRing::create()?)),
+        };
+
+        let path = ring.lock().unwrap().path();
+        self.state.update(|s| {
+            s.shared_frames.get_or_insert(ring);
+            s.shared_frame_subscribers += 1;
+        });
+        self.invalidate();
+
+        Ok(path)
+    }
+
+    pub fn disable_shared_frames(&self) {
+        self.state.update(|s| {
+            s.shared_frame_subscribers = s.shared_frame_subscribers.saturating_sub(1);
+            if s.shared_frame_subscribers == 0 {
+                s.shared_frames = None;
+            }
+        });
+    }
+
+    pub fn subscriber_stats(&self) -> HashMap<i32, SubscriberStats> {
+        self.state.subscriber_stats()
+    }
+
     pub fn undo(&self) {
         if let Some(frame) = self.inner.get_main_frame().unwrap() {
             let _ = frame.undo();
@@ -206,6 +317,40 @@ impl Browser {
         }
     }
 
+    /// Searches the page for `text`. `find_next` continues the previous search,
+    /// results are reported with [`TabMessage::FindResult`](crate::TabMessage::FindResult).
+    pub fn find(&self, text: &str, forward: bool, match_case: bool, find_next: bool) {
+        match self.inner.get_host() {
+            Ok(host) => _ = host.find(text, forward, match_case, find_next),
+            Err(_) => error!("failed to find {:?}: browser host is gone", text),
+        }
+    }
+
+    pub fn stop_finding(&self, clear_selection: bool) {
+        if let Ok(host) = self.inner.get_host() {
+            _ = host.stop_finding(clear_selection);
+        }
+    }
+
+    pub fn set_audio_muted(&self, muted: bool) {
+        let Ok(host) = self.inner.get_host() else {
+            error!("failed to mute audio: browser host is gone");
+            return;
+        };
+        _ = host.set_audio_muted(muted);
+        self.state.update(|s| s.audio_muted = muted);
+        self.state.notify(TabMessage::AudioMuted(muted));
+    }
+
+    /// Sets the content inserted by the next paste into the page.
+    /// It is kept until the page navigates to another origin.
+    pub fn set_clipboard(&self, clipboard: ClipboardData) {
+        if let Some(frame) = self.inner.get_main_frame().unwrap() {
+            send_clipboard(&frame, Some(&clipboard));
+        }
+        self.state.update(|s| s.clipboard = Some(clipboard));
+    }
+
     pub fn delete(&self) {
         if let Some(frame) = self.inner.get_main_frame().unwrap() {
             let _ = frame.delete();
@@ -230,6 +375,11 @@ impl Browser {
         }
     }
 
+    /// Answers a [`TabMessage::PermissionRequest`].
+    pub fn respond_permission(&self, id: u64, allowed: bool, remember: bool) -> anyhow::Result<()> {
+        permissions::respond(&self.state, id, allowed, remember)
+    }
+
     pub fn cancel_downloading(&self, id: u32) {
         let callback = self.state.update_and_return(|s| s.downloads.remove(&id));
         if let Some(callback) = callback {
@@ -248,13 +398,15 @@ struct CreateBrowserTaskCallback {
     width: u32,
     height: u32,
     dpr: f64,
+    frame_rate: i32,
     url: String,
+    preferences: Arc<Preferences>,
 }
 
 impl CefTaskCallbacks for CreateBrowserTaskCallback {
     fn execute(&mut self) {
         let window_info = WindowInfo::new().windowless_rendering_enabled(true);
-        let settings = BrowserSettings::new().windowless_frame_rate(60);
+        let settings = BrowserSettings::new().windowless_frame_rate(self.frame_rate);
         let state = SharedBrowserState::new(state::BrowserState {
             title: "".to_string(),
             url: self.url.clone(),
@@ -262,16 +414,42 @@ impl CefTaskCallbacks for CreateBrowserTaskCallback {
             load_state: LoadState::default(),
             navigation_started: false,
             cursor: "Pointer".to_string(),
+            custom_cursor: None,
             width: self.width,
             height: self.height,
             dpr: self.dpr,
+            frame_rate: self.frame_rate,
+            adaptive_frame_rate: false,
             active: false,
 
+            framebuffer: Arc::new(Mutex::new(Framebuffer::new(
+                self.width,
+                self.height,
+                self.dpr,
+            ))),
+            paint_count: 0,
+            capture_requests: 0,
+            popup: None,
+            shared_frames: None,
+            shared_frame_subscribers: 0,
+
+            emulation: EmulationSettings::default(),
+            zoom_level: 0.0,
+            zoom_origin: None,
+            preferences: self.preferences.clone(),
+
             external_link: "".to_string(),
+            audio: None,
+            audible: false,
+            audio_muted: false,
 
             input: state::InputState::new(),
+            drag: None,
+            dropped_files: None,
+            clipboard: None,
 
             file_dialog_callback: None,
+            permission_requests: HashMap::new(),
             downloads: HashMap::new(),
 
             js_messages: HashMap::new(),
@@ -291,7 +469,17 @@ impl CefTaskCallbacks for CreateBrowserTaskCallback {
 
         let mouse = mouse::Mouse::new(inner.clone(), state.clone());
         let keyboard = keyboard::Keyboard::new(inner.clone(), state.clone());
-        let automation = automation::Automation::new(inner.clone(), state.clone(), mouse.clone());
+        let touch = touch::Touch::new(inner.clone(), state.clone());
+        let drag = drag::Drag::new(inner.clone(), state.clone());
+        let zoom = zoom::Zoom::new(inner.clone(), state.clone());
+        let devtools = Arc::new(devtools::DevTools::new(inner.clone()));
+        let automation = automation::Automation::new(
+            inner.clone(),
+            state.clone(),
+            mouse.clone(),
+            devtools.clone(),
+        );
+        let emulation = emulation::Emulation::new(devtools, state.clone());
 
         self.tx
             .send(Browser {
@@ -299,9 +487,26 @@ impl CefTaskCallbacks for CreateBrowserTaskCallback {
                 state,
                 mouse,
                 keyboard,
+                touch,
+                drag,
+                zoom,
                 automation,
+                emulation,
                 counter: 0,
             })
             .expect("failed to send created browser");
     }
 }
+
+/// Makes `clipboard` the content pasted by the clipboard bridge of the render process,
+/// see [`crate::CLIPBOARD_BRIDGE`]. `None` clears it.
+pub(crate) fn send_clipboard(frame: &cef_ui::Frame, clipboard: Option<&ClipboardData>) {
+    let clipboard = clipboard
+        .map(|clipboard| serde_json::to_string(clipboard).expect("failed to serialize clipboard"))
+        .unwrap_or_default();
+    let message = ProcessMessage::new(SET_CLIPBOARD_MESSAGE);
+    if let Some(args) = message.get_argument_list().ok().flatten() {
+        _ = args.set_string(0, &clipboard);
+    }
+    _ = frame.send_process_message(ProcessId::Renderer, message);
+}
@@ -1,5 +1,9 @@
-use crate::{state::SharedBrowserState, MouseButton};
+use crate::{
+    state::{DragState, SharedBrowserState},
+    MouseButton,
+};
 use cef_ui::{Browser, MouseButtonType, MouseEvent};
+use log::error;
 
 pub struct Mouse {
     inner: Browser,
@@ -20,27 +24,48 @@ impl Mouse {
         Mouse { inner, state }
     }
 
-    pub fn move_to(&self, x: i32, y: i32) {
+    /// Moves the cursor, `mouse_leave` tells the page that the cursor has left the view.
+    pub fn move_to(&self, x: i32, y: i32, mouse_leave: bool) {
         let modifiers = self.state.read(|s| s.input.get_event_flags());
         let event = MouseEvent { x, y, modifiers };
 
+        let drag = self
+            .state
+            .read(|s| s.drag.as_ref().map(|drag| drag.allowed_ops));
+        if let Some(allowed_ops) = drag {
+            let Ok(host) = self.inner.get_host() else {
+                error!("failed to drag: browser host is gone");
+                return;
+            };
+            _ = host.drag_target_drag_over(&event, allowed_ops);
+            return;
+        }
+
         self.inner
             .get_host()
             .unwrap()
-            .send_mouse_move_event(&event, false)
+            .send_mouse_move_event(&event, mouse_leave)
             .expect("failed to send mouse move event");
     }
 
-    pub fn click(&self, x: i32, y: i32, button: MouseButton, down: bool) {
-        if button == MouseButton::Left {
-            self.state.update(|state| {
-                state.input.left_mouse_button_down = down;
-            });
-        }
-
-        let modifiers = self.state.read(|s| s.input.get_event_flags());
+    /// Presses or releases a button. Without `click_count`, presses of the same button
+    /// that follow each other quickly at the same position become double and triple clicks.
+    pub fn click(&self, x: i32, y: i32, button: MouseButton, down: bool, click_count: Option<i32>) {
+        let (modifiers, click_count) = self.state.update_and_return(|state| {
+            state.input.update_mouse_button(button, down);
+            let click_count = state.input.click_count(x, y, button, down, click_count);
+            (state.input.get_event_flags(), click_count)
+        });
         let event = MouseEvent { x, y, modifiers };
 
+        if button == MouseButton::Left && !down {
+            let drag = self.state.update_and_return(|s| s.drag.take());
+            if let Some(drag) = drag {
+                self.end_drag(&event, drag);
+                return;
+            }
+        }
+
         let button = match button {
             MouseButton::Left => MouseButtonType::Left,
             MouseButton::Middle => MouseButtonType::Middle,
@@ -50,7 +75,7 @@ impl Mouse {
         self.inner
             .get_host()
             .unwrap()
-            .send_mouse_click_event(&event, button, !down, 1)
+            .send_mouse_click_event(&event, button, !down, click_count)
             .expect("failed to send mouse click event");
     }
 
@@ -63,4 +88,15 @@ impl Mouse {
             .send_mouse_wheel_event(&event, dx, dy)
             .expect("failed to send mouse wheel event");
     }
+
+    /// Drops a drag started by the page at the cursor position.
+    fn end_drag(&self, event: &MouseEvent, drag: DragState) {
+        let Ok(host) = self.inner.get_host() else {
+            error!("failed to drop: browser host is gone");
+            return;
+        };
+        _ = host.drag_target_drop(event);
+        _ = host.drag_source_ended_at(event.x, event.y, drag.operation);
+        _ = host.drag_source_system_drag_ended();
+    }
 }
@@ -0,0 +1,259 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use cef_ui::{MediaAccessCallback, PermissionPromptCallback, PermissionRequestResult};
+
+use crate::{
+    browser::{preferences, state::SharedBrowserState},
+    PermissionRequest, TabMessage,
+};
+
+/// Bits of `cef_media_access_permission_types_t`.
+const MEDIA_PERMISSIONS: &[(u32, &str)] = &[
+    (1 << 0, "microphone"),
+    (1 << 1, "camera"),
+    (1 << 2, "desktopAudio"),
+    (1 << 3, "desktopVideo"),
+];
+
+/// Bits of `cef_permission_request_types_t`.
+const PROMPT_PERMISSIONS: &[(u32, &str)] = &[
+    (1 << 0, "arSession"),
+    (1 << 1, "cameraPanTiltZoom"),
+    (1 << 2, "camera"),
+    (1 << 3, "capturedSurfaceControl"),
+    (1 << 4, "clipboard"),
+    (1 << 5, "topLevelStorageAccess"),
+    (1 << 6, "diskQuota"),
+    (1 << 7, "localFonts"),
+    (1 << 8, "geolocation"),
+    (1 << 9, "identityProvider"),
+    (1 << 10, "idleDetection"),
+    (1 << 11, "microphone"),
+    (1 << 12, "midiSysex"),
+    (1 << 13, "multipleDownloads"),
+    (1 << 14, "notifications"),
+    (1 << 15, "keyboardLock"),
+    (1 << 16, "pointerLock"),
+    (1 << 17, "protectedMediaIdentifier"),
+    (1 << 18, "registerProtocolHandler"),
+    (1 << 19, "storageAccess"),
+    (1 << 20, "vrSession"),
+    (1 << 21, "windowManagement"),
+    (1 << 22, "fileSystemAccess"),
+];
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+enum PermissionCallback {
+    Media(MediaAccessCallback),
+    Prompt {
+        prompt_id: u64,
+        callback: PermissionPromptCallback,
+    },
+}
+
+/// A permission request waiting for a client to respond.
+pub struct PendingPermission {
+    pub request: PermissionRequest,
+    callback: PermissionCallback,
+}
+
+impl PendingPermission {
+    fn prompt_id(&self) -> Option<u64> {
+        match self.callback {
+            PermissionCallback::Prompt { prompt_id, .. } => Some(prompt_id),
+            PermissionCallback::Media(_) => None,
+        }
+    }
+
+    fn resolve(self, allowed: bool) {
+        match self.callback {
+            PermissionCallback::Media(callback) if allowed => {
+                _ = callback.cont(mask(MEDIA_PERMISSIONS, &self.request.permissions));
+            }
+            PermissionCallback::Media(callback) => _ = callback.cancel(),
+            PermissionCallback::Prompt { callback, .. } => {
+                _ = callback.cont(if allowed {
+                    PermissionRequestResult::Accept
+                } else {
+                    PermissionRequestResult::Deny
+                });
+            }
+        }
+    }
+}
+
+/// Handles a request for camera, microphone or screen capture. Returns false for
+/// requests of unknown permissions, which CEF then denies.
+pub(crate) fn request_media_access(
+    state: &SharedBrowserState,
+    origin: &str,
+    permissions: u32,
+    callback: MediaAccessCallback,
+) -> bool {
+    let permissions = names(MEDIA_PERMISSIONS, permissions);
+    if permissions.is_empty() {
+        return false;
+    }
+
+    // Media requests may be granted partially.
+    let decisions = permissions
+        .iter()
+        .map(|permission| remembered(state, origin, permission))
+        .collect::<Option<Vec<_>>>();
+    if let Some(decisions) = decisions {
+        let allowed = permissions
+            .iter()
+            .zip(decisions)
+            .filter(|(_, allowed)| *allowed)
+            .map(|(permission, _)| permission.clone())
+            .collect::<Vec<_>>();
+        match mask(MEDIA_PERMISSIONS, &allowed) {
+            0 => _ = callback.cancel(),
+            mask => _ = callback.cont(mask),
+        }
+        return true;
+    }
+
+    ask(
+        state,
+        origin,
+        permissions,
+        PermissionCallback::Media(callback),
+    );
+    true
+}
+
+/// Handles a permission prompt, e.g. for geolocation or notifications.
+pub(crate) fn show_prompt(
+    state: &SharedBrowserState,
+    prompt_id: u64,
+    origin: &str,
+    permissions: u32,
+    callback: PermissionPromptCallback,
+) -> bool {
+    let permissions = names(PROMPT_PERMISSIONS, permissions);
+    if permissions.is_empty() {
+        return false;
+    }
+
+    let decisions = permissions
+        .iter()
+        .map(|permission| remembered(state, origin, permission))
+        .collect::<Option<Vec<_>>>();
+    if let Some(decisions) = decisions {
+        _ = callback.cont(if decisions.iter().all(|allowed| *allowed) {
+            PermissionRequestResult::Accept
+        } else {
+            PermissionRequestResult::Deny
+        });
+        return true;
+    }
+
+    ask(
+        state,
+        origin,
+        permissions,
+        PermissionCallback::Prompt {
+            prompt_id,
+            callback,
+        },
+    );
+    true
+}
+
+/// The prompt has been dismissed by CEF, e.g. because the page navigated away.
+pub(crate) fn dismiss_prompt(state: &SharedBrowserState, prompt_id: u64) {
+    let id = state.update_and_return(|s| {
+        let id = s
+            .permission_requests
+            .iter()
+            .find(|(_, pending)| pending.prompt_id() == Some(prompt_id))
+            .map(|(id, _)| *id)?;
+        s.permission_requests.remove(&id);
+        Some(id)
+    });
+
+    if let Some(id) = id {
+        state.notify(TabMessage::PermissionDismissed(id));
+    }
+}
+
+/// Resolves a pending request. The decision is remembered for the origin of the
+/// request unless `remember` is false.
+pub(crate) fn respond(
+    state: &SharedBrowserState,
+    id: u64,
+    allowed: bool,
+    remember: bool,
+) -> Result<()> {
+    let pending = state
+        .update_and_return(|s| s.permission_requests.remove(&id))
+        .ok_or_else(|| anyhow::anyhow!("no pending permission request {}", id))?;
+
+    if remember {
+        if let Some(origin) = preferences::origin(&pending.request.origin) {
+            state.read(|s| s.preferences.clone()).set_permissions(
+                &origin,
+                &pending.request.permissions,
+                allowed,
+            );
+        }
+    }
+
+    state.notify(TabMessage::PermissionDismissed(id));
+    pending.resolve(allowed);
+    Ok(())
+}
+
+/// Cancels all pending requests, e.g. when the tab is closed.
+pub(crate) fn cancel_all(state: &SharedBrowserState) {
+    let pending = state.update_and_return(|s| std::mem::take(&mut s.permission_requests));
+    for (_, pending) in pending {
+        pending.resolve(false);
+    }
+}
+
+fn ask(
+    state: &SharedBrowserState,
+    origin: &str,
+    permissions: Vec<String>,
+    callback: PermissionCallback,
+) {
+    let request = PermissionRequest {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        origin: origin.to_string(),
+        permissions,
+    };
+    state.update(|s| {
+        s.permission_requests.insert(
+            request.id,
+            PendingPermission {
+                request: request.clone(),
+                callback,
+            },
+        );
+    });
+    state.notify(TabMessage::PermissionRequest(request));
+}
+
+fn remembered(state: &SharedBrowserState, origin: &str, permission: &str) -> Option<bool> {
+    let origin = preferences::origin(origin)?;
+    state.read(|s| s.preferences.permission(&origin, permission))
+}
+
+fn names(table: &[(u32, &str)], permissions: u32) -> Vec<String> {
+    table
+        .iter()
+        .filter(|(bit, _)| permissions & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+fn mask(table: &[(u32, &str)], permissions: &[String]) -> u32 {
+    table
+        .iter()
+        .filter(|(_, name)| permissions.iter().any(|p| p == name))
+        .fold(0, |mask, (bit, _)| mask | bit)
+}
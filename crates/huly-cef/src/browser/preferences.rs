@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+const PREFERENCES_FILE: &str = "preferences.json";
+
+#[derive(Default, Serialize, Deserialize)]
+struct PreferencesData {
+    #[serde(default)]
+    zoom_levels: HashMap<String, f64>,
+    /// Permission decisions by origin and permission name.
+    #[serde(default)]
+    permissions: HashMap<String, HashMap<String, bool>>,
+}
+
+/// Per-origin settings shared by all tabs and kept in the cache directory, the way
+/// Chrome keeps site settings in the profile.
+pub struct Preferences {
+    path: PathBuf,
+    data: Mutex<PreferencesData>,
+}
+
+impl Preferences {
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join(PREFERENCES_FILE);
+        let data = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                error!("failed to parse {}: {}", path.display(), e);
+                PreferencesData::default()
+            }),
+            Err(_) => PreferencesData::default(),
+        };
+
+        Self {
+            path,
+            data: Mutex::new(data),
+        }
+    }
+
+    pub fn zoom_level(&self, origin: &str) -> f64 {
+        let data = self.data.lock().unwrap();
+        data.zoom_levels.get(origin).copied().unwrap_or_default()
+    }
+
+    pub fn set_zoom_level(&self, origin: &str, level: f64) {
+        let mut data = self.data.lock().unwrap();
+        if level == 0.0 {
+            data.zoom_levels.remove(origin);
+        } else {
+            data.zoom_levels.insert(origin.to_string(), level);
+        }
+        self.save(&data);
+    }
+
+    /// Returns whether `permission` was granted to `origin`, `None` if it hasn't been decided.
+    pub fn permission(&self, origin: &str, permission: &str) -> Option<bool> {
+        let data = self.data.lock().unwrap();
+        data.permissions.get(origin)?.get(permission).copied()
+    }
+
+    pub fn set_permissions(&self, origin: &str, permissions: &[String], allowed: bool) {
+        let mut data = self.data.lock().unwrap();
+        let decisions = data.permissions.entry(origin.to_string()).or_default();
+        for permission in permissions {
+            decisions.insert(permission.clone(), allowed);
+        }
+        self.save(&data);
+    }
+
+    fn save(&self, data: &PreferencesData) {
+        let content = serde_json::to_string_pretty(data).expect("failed to serialize preferences");
+        if let Err(e) = fs::write(&self.path, content) {
+            error!("failed to save {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Returns the origin preferences are kept for, `None` for opaque origins
+/// such as `about:blank` or `data:` URLs.
+pub fn origin(url: &str) -> Option<String> {
+    let origin = url::Url::parse(url).ok()?.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
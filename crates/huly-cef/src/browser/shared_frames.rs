@@ -0,0 +1,117 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use log::error;
+
+use crate::{DirtyRect, Framebuffer, SharedFrame};
+
+/// Ring of framebuffers in a shared-memory file, so that clients on the same host
+/// can map frames instead of receiving them over the tab socket.
+///
+/// The file starts with a 16-byte header: the magic `HCEF`, the layout version,
+/// the slot count and a reserved field, all u32 little-endian. It is followed by
+/// `slot count` slots. Each slot starts with the frame sequence (u64), width and
+/// height (u32), followed by the RGBA pixels. A slot is rewritten every
+/// `slot count` frames, its sequence is written last, so a client should check that
+/// it still matches [`SharedFrame::sequence`] after copying the pixels.
+pub const SHARED_FRAMES_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 4] = b"HCEF";
+const SLOT_COUNT: u32 = 3;
+const HEADER_SIZE: u64 = 16;
+const SLOT_HEADER_SIZE: u64 = 16;
+
+pub struct SharedFrameRing {
+    file: File,
+    path: PathBuf,
+    slot_size: u64,
+    sequence: u64,
+}
+
+impl SharedFrameRing {
+    pub fn create() -> Result<Self> {
+        let dir = PathBuf::from("/dev/shm");
+        let dir = if dir.is_dir() {
+            dir
+        } else {
+            std::env::temp_dir()
+        };
+        let path = dir.join(format!("huly-cef-frames-{}", uuid::Uuid::new_v4()));
+
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&SHARED_FRAMES_VERSION.to_le_bytes());
+        header.extend_from_slice(&SLOT_COUNT.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        file.write_all(&header)?;
+
+        Ok(Self {
+            file,
+            path,
+            slot_size: 0,
+            sequence: 0,
+        })
+    }
+
+    pub fn path(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    /// Copies the framebuffer into the next slot. The file grows when the frame
+    /// no longer fits into a slot.
+    pub fn write(
+        &mut self,
+        framebuffer: &Framebuffer,
+        dirty_rects: Vec<DirtyRect>,
+    ) -> Result<SharedFrame> {
+        let length = framebuffer.data.len() as u64;
+        if length > self.slot_size {
+            self.slot_size = length;
+            self.file
+                .set_len(HEADER_SIZE + SLOT_COUNT as u64 * (SLOT_HEADER_SIZE + length))?;
+        }
+
+        self.sequence += 1;
+        let slot = (self.sequence % SLOT_COUNT as u64) as u32;
+        let offset = HEADER_SIZE + slot as u64 * (SLOT_HEADER_SIZE + self.slot_size);
+
+        self.file.seek(SeekFrom::Start(offset + SLOT_HEADER_SIZE))?;
+        self.file.write_all(&framebuffer.data)?;
+
+        let mut header = Vec::with_capacity(SLOT_HEADER_SIZE as usize);
+        header.extend_from_slice(&self.sequence.to_le_bytes());
+        header.extend_from_slice(&framebuffer.width.to_le_bytes());
+        header.extend_from_slice(&framebuffer.height.to_le_bytes());
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&header)?;
+
+        Ok(SharedFrame {
+            path: self.path(),
+            sequence: self.sequence,
+            slot,
+            offset: offset + SLOT_HEADER_SIZE,
+            width: framebuffer.width,
+            height: framebuffer.height,
+            dpr: framebuffer.dpr,
+            dirty_rects,
+        })
+    }
+}
+
+impl Drop for SharedFrameRing {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            error!("failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
@@ -1,22 +1,46 @@
 use anyhow::Result;
-use cef_ui::{DownloadItemCallback, EventFlags, FileDialogCallback};
+use cef_ui::{DownloadItemCallback, DragOperationsMask, EventFlags, FileDialogCallback};
 use log::{error, info};
 
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use tokio::{
-    sync::{mpsc::UnboundedSender, oneshot, Notify},
+    sync::{oneshot, Notify},
     time::error::Elapsed,
 };
 
-use crate::{messages::TabMessage, LoadState, TabMessageType};
+use crate::{
+    browser::{
+        permissions::PendingPermission,
+        preferences::Preferences,
+        shared_frames::SharedFrameRing,
+        subscriber::{SubscriberSender, SubscriberStats},
+    },
+    messages::TabMessage,
+    AudioStream, ClipboardData, CustomCursor, EmulationSettings, Framebuffer, LoadState,
+    MouseButton, PopupLayer, TabMessageType,
+};
 
 type TabMessageCallback = Box<dyn Fn(TabMessage) + Send + Sync>;
 
+// Presses of the same button closer than this in time and space make a multi-click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+const MULTI_CLICK_DISTANCE: i32 = 4;
+
+#[derive(Clone, Copy)]
+pub struct Click {
+    pub x: i32,
+    pub y: i32,
+    pub button: MouseButton,
+    pub count: i32,
+    pub time: Instant,
+}
+
 #[derive(Default)]
 pub struct InputState {
     pub ctrl_down: bool,
@@ -24,6 +48,9 @@ pub struct InputState {
     pub alt_down: bool,
     pub meta_down: bool,
     pub left_mouse_button_down: bool,
+    pub middle_mouse_button_down: bool,
+    pub right_mouse_button_down: bool,
+    pub last_click: Option<Click>,
 }
 
 impl InputState {
@@ -49,6 +76,12 @@ impl InputState {
         if self.left_mouse_button_down {
             flags |= EventFlags::LeftMouseButton;
         }
+        if self.middle_mouse_button_down {
+            flags |= EventFlags::MiddleMouseButton;
+        }
+        if self.right_mouse_button_down {
+            flags |= EventFlags::RightMouseButton;
+        }
 
         flags
     }
@@ -59,6 +92,60 @@ impl InputState {
         self.alt_down = alt;
         self.meta_down = meta;
     }
+
+    pub fn update_mouse_button(&mut self, button: MouseButton, down: bool) {
+        match button {
+            MouseButton::Left => self.left_mouse_button_down = down,
+            MouseButton::Middle => self.middle_mouse_button_down = down,
+            MouseButton::Right => self.right_mouse_button_down = down,
+        }
+    }
+
+    /// Returns the click count of a button press or release. A press continues the
+    /// previous click if it is close enough to it, a release reuses the count of the
+    /// press. `click_count` overrides the detection.
+    pub fn click_count(
+        &mut self,
+        x: i32,
+        y: i32,
+        button: MouseButton,
+        down: bool,
+        click_count: Option<i32>,
+    ) -> i32 {
+        let last_click = self.last_click.filter(|click| click.button == button);
+        if !down {
+            return click_count.unwrap_or(last_click.map_or(1, |click| click.count));
+        }
+
+        let now = Instant::now();
+        let count = click_count.unwrap_or(match last_click {
+            Some(click)
+                if now.duration_since(click.time) <= MULTI_CLICK_INTERVAL
+                    && (click.x - x).abs() <= MULTI_CLICK_DISTANCE
+                    && (click.y - y).abs() <= MULTI_CLICK_DISTANCE =>
+            {
+                click.count + 1
+            }
+            _ => 1,
+        });
+
+        self.last_click = Some(Click {
+            x,
+            y,
+            button,
+            count,
+            time: now,
+        });
+        count
+    }
+}
+
+/// A drag started by the page, e.g. a card being dragged to another column.
+/// Mouse events move and drop it until the left button is released.
+pub struct DragState {
+    pub allowed_ops: DragOperationsMask,
+    /// The operation accepted by the element under the cursor.
+    pub operation: DragOperationsMask,
 }
 
 pub struct BrowserState {
@@ -68,21 +155,50 @@ pub struct BrowserState {
     pub load_state: LoadState,
     pub navigation_started: bool,
     pub cursor: String,
+    pub custom_cursor: Option<CustomCursor>,
     pub width: u32,
     pub height: u32,
     pub dpr: f64,
+    pub frame_rate: i32,
+    pub adaptive_frame_rate: bool,
     pub active: bool,
 
+    pub framebuffer: Arc<Mutex<Framebuffer>>,
+    /// Number of views painted into the framebuffer so far.
+    pub paint_count: u64,
+    /// Pending framebuffer captures, a hidden tab keeps painting while there are any.
+    pub capture_requests: u32,
+    pub popup: Option<PopupLayer>,
+    pub shared_frames: Option<Arc<Mutex<SharedFrameRing>>>,
+    pub shared_frame_subscribers: usize,
+
+    pub emulation: EmulationSettings,
+    pub zoom_level: f64,
+    /// Origin of the page the zoom level belongs to.
+    pub zoom_origin: Option<String>,
+    pub preferences: Arc<Preferences>,
+
     pub external_link: String,
+    /// Format of the audio stream while the tab plays audio.
+    pub audio: Option<AudioStream>,
+    /// Whether the tab is producing sound, a muted tab still can be audible.
+    pub audible: bool,
+    pub audio_muted: bool,
 
     pub input: InputState,
+    pub drag: Option<DragState>,
+    /// Directory with the files of the last drop from outside of the browser.
+    pub dropped_files: Option<PathBuf>,
+    /// Content pasted into the page, the last copy or the one set by the client.
+    pub clipboard: Option<ClipboardData>,
 
     pub file_dialog_callback: Option<FileDialogCallback>,
+    pub permission_requests: HashMap<u64, PendingPermission>,
 
     pub downloads: HashMap<u32, DownloadItemCallback>,
 
     pub js_messages: HashMap<String, oneshot::Sender<String>>,
-    pub subscribers: HashMap<i32, UnboundedSender<TabMessage>>,
+    pub subscribers: HashMap<i32, SubscriberSender>,
     pub single_event_subscribers: HashMap<TabMessageType, TabMessageCallback>,
 }
 
@@ -117,7 +233,7 @@ impl SharedBrowserState {
         result
     }
 
-    pub fn subscribe(&self, id: i32, tx: UnboundedSender<TabMessage>) {
+    pub fn subscribe(&self, id: i32, tx: SubscriberSender) {
         let mut state = self.state.lock().expect("Browser state lock poisoned");
         state.subscribers.insert(id, tx);
     }
@@ -127,11 +243,23 @@ impl SharedBrowserState {
         state.subscribers.remove(&id);
     }
 
+    pub fn subscriber_stats(&self) -> HashMap<i32, SubscriberStats> {
+        let state = self.state.lock().expect("Browser state lock poisoned");
+        state
+            .subscribers
+            .iter()
+            .map(|(id, tx)| (*id, tx.stats()))
+            .collect()
+    }
+
     pub fn notify(&self, message: TabMessage) {
         let state = self.state.lock().expect("Browser state lock poisoned");
-        for (_, tx) in &state.subscribers {
-            if let Err(e) = tx.send(message.clone()) {
-                error!("Failed to send message to subscriber: {}", e);
+        for (id, tx) in &state.subscribers {
+            if tx.send(message.clone()).is_err() {
+                error!(
+                    "Failed to send message to subscriber {}: receiver is closed",
+                    id
+                );
             }
         }
 
@@ -183,3 +311,58 @@ impl Drop for SharedBrowserState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut InputState, x: i32, y: i32, button: MouseButton) -> i32 {
+        let count = input.click_count(x, y, button, true, None);
+        assert_eq!(input.click_count(x, y, button, false, None), count);
+        count
+    }
+
+    #[test]
+    fn consecutive_presses_make_a_multi_click() {
+        let mut input = InputState::new();
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 1);
+        assert_eq!(press(&mut input, 12, 8, MouseButton::Left), 2);
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 3);
+    }
+
+    #[test]
+    fn distant_presses_start_a_new_click() {
+        let mut input = InputState::new();
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 1);
+        assert_eq!(press(&mut input, 20, 10, MouseButton::Left), 1);
+        assert_eq!(press(&mut input, 20, 10, MouseButton::Right), 1);
+        assert_eq!(press(&mut input, 20, 10, MouseButton::Left), 1);
+    }
+
+    #[test]
+    fn late_presses_start_a_new_click() {
+        let mut input = InputState::new();
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 1);
+
+        let click = input.last_click.as_mut().unwrap();
+        click.time = Instant::now()
+            .checked_sub(MULTI_CLICK_INTERVAL + Duration::from_millis(1))
+            .unwrap();
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 1);
+    }
+
+    #[test]
+    fn explicit_click_count() {
+        let mut input = InputState::new();
+        assert_eq!(
+            input.click_count(10, 10, MouseButton::Left, true, Some(2)),
+            2
+        );
+        assert_eq!(input.click_count(10, 10, MouseButton::Left, false, None), 2);
+        assert_eq!(press(&mut input, 10, 10, MouseButton::Left), 3);
+        assert_eq!(
+            input.click_count(10, 10, MouseButton::Left, false, Some(1)),
+            1
+        );
+    }
+}
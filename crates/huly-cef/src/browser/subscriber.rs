@@ -0,0 +1,529 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::{DirtyRect, TabMessage};
+
+/// Dirty rects of a coalesced frame are replaced with their bounding box once there
+/// are more of them, so that they don't pile up while a subscriber is stalled.
+const MAX_DIRTY_RECTS: usize = 16;
+
+/// Audio packets waiting for a subscriber, about a second of audio. Older packets
+/// are dropped when the subscriber doesn't keep up.
+const MAX_QUEUED_AUDIO_PACKETS: usize = 100;
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberStats {
+    /// Control events waiting to be received.
+    pub queued_events: usize,
+    /// Frames and popups waiting to be received, at most one of each.
+    pub queued_frames: usize,
+    /// Frames received by the subscriber.
+    pub sent_frames: u64,
+    /// Frames merged into a newer one because the subscriber didn't keep up.
+    pub dropped_frames: u64,
+    /// Audio packets dropped because the subscriber didn't keep up.
+    pub dropped_audio_packets: u64,
+}
+
+#[derive(Default)]
+struct Queue {
+    events: VecDeque<TabMessage>,
+    frame: Option<TabMessage>,
+    popup: Option<TabMessage>,
+    audio: VecDeque<TabMessage>,
+    sent_frames: u64,
+    dropped_frames: u64,
+    dropped_audio_packets: u64,
+    senders: usize,
+    receiver_alive: bool,
+    shared_frames: bool,
+    audio_enabled: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    notify: Notify,
+}
+
+/// Creates a per-subscriber channel. Control events are always delivered in order,
+/// while frames and popups are coalesced so that only the latest one waits in the queue.
+/// Audio packets are delivered in order with the start and stop of their stream.
+pub fn channel() -> (SubscriberSender, SubscriberReceiver) {
+    let shared = Arc::new(Shared::default());
+    {
+        let mut queue = shared.queue.lock().unwrap();
+        queue.senders = 1;
+        queue.receiver_alive = true;
+    }
+
+    (
+        SubscriberSender {
+            shared: shared.clone(),
+        },
+        SubscriberReceiver { shared },
+    )
+}
+
+pub struct SubscriberSender {
+    shared: Arc<Shared>,
+}
+
+impl SubscriberSender {
+    pub fn send(&self, message: TabMessage) -> Result<(), TabMessage> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if !queue.receiver_alive {
+            return Err(message);
+        }
+
+        match message {
+            TabMessage::Frame { .. } if queue.shared_frames => return Ok(()),
+            TabMessage::FrameReady(_) if !queue.shared_frames => return Ok(()),
+            TabMessage::AudioPacket(_) if !queue.audio_enabled => return Ok(()),
+            TabMessage::AudioPacket(packet) => {
+                if queue.audio.len() >= MAX_QUEUED_AUDIO_PACKETS {
+                    // Only packets are dropped, so that streams keep their start and stop.
+                    let oldest = queue
+                        .audio
+                        .iter()
+                        .position(|message| matches!(message, TabMessage::AudioPacket(_)));
+                    if let Some(oldest) = oldest {
+                        queue.audio.remove(oldest);
+                        queue.dropped_audio_packets += 1;
+                    }
+                }
+                queue.audio.push_back(TabMessage::AudioPacket(packet));
+            }
+            // Queued with the packets, so that they can't overtake the packets of the
+            // previous stream.
+            message @ (TabMessage::AudioStarted(_) | TabMessage::AudioStopped)
+                if queue.audio_enabled =>
+            {
+                queue.audio.push_back(message);
+            }
+            TabMessage::Frame {
+                framebuffer,
+                dirty_rects,
+                sequence,
+                timestamp,
+            } => {
+                let dirty_rects = match queue.frame.take() {
+                    Some(TabMessage::Frame {
+                        dirty_rects: pending,
+                        ..
+                    }) => {
+                        queue.dropped_frames += 1;
+                        merge_dirty_rects(pending, dirty_rects)
+                    }
+                    _ => dirty_rects,
+                };
+
+                queue.frame = Some(TabMessage::Frame {
+                    framebuffer,
+                    dirty_rects,
+                    sequence,
+                    timestamp,
+                });
+            }
+            TabMessage::FrameReady(mut frame) => {
+                if let Some(TabMessage::FrameReady(pending)) = queue.frame.take() {
+                    queue.dropped_frames += 1;
+                    frame.dirty_rects = merge_dirty_rects(pending.dirty_rects, frame.dirty_rects);
+                }
+                queue.frame = Some(TabMessage::FrameReady(frame));
+            }
+            TabMessage::Popup(popup) => {
+                if queue.popup.replace(TabMessage::Popup(popup)).is_some() {
+                    queue.dropped_frames += 1;
+                }
+            }
+            TabMessage::PopupHidden => {
+                queue.popup = None;
+                queue.events.push_back(TabMessage::PopupHidden);
+            }
+            message => queue.events.push_back(message),
+        }
+
+        self.shared.notify.notify_one();
+        Ok(())
+    }
+
+    /// Switches the subscriber from [`TabMessage::Frame`] to [`TabMessage::FrameReady`].
+    pub fn use_shared_frames(&self, enabled: bool) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.shared_frames = enabled;
+        queue.frame = None;
+    }
+
+    /// Audio packets are only delivered to subscribers that enabled them.
+    pub fn enable_audio(&self, enabled: bool) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.audio_enabled = enabled;
+        queue.audio.clear();
+    }
+
+    pub fn stats(&self) -> SubscriberStats {
+        let queue = self.shared.queue.lock().unwrap();
+        SubscriberStats {
+            queued_events: queue.events.len(),
+            queued_frames: queue.frame.iter().count() + queue.popup.iter().count(),
+            sent_frames: queue.sent_frames,
+            dropped_frames: queue.dropped_frames,
+            dropped_audio_packets: queue.dropped_audio_packets,
+        }
+    }
+}
+
+impl Clone for SubscriberSender {
+    fn clone(&self) -> Self {
+        self.shared.queue.lock().unwrap().senders += 1;
+        SubscriberSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for SubscriberSender {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().senders -= 1;
+        self.shared.notify.notify_one();
+    }
+}
+
+pub struct SubscriberReceiver {
+    shared: Arc<Shared>,
+}
+
+impl SubscriberReceiver {
+    /// Waits for the next message. Returns `None` once all senders are gone
+    /// and the queue is drained.
+    pub async fn recv(&mut self) -> Option<TabMessage> {
+        loop {
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                if let Some(event) = queue.events.pop_front() {
+                    return Some(event);
+                }
+
+                if let Some(packet) = queue.audio.pop_front() {
+                    return Some(packet);
+                }
+
+                if let Some(frame) = queue.frame.take() {
+                    queue.sent_frames += 1;
+                    return Some(frame);
+                }
+
+                if let Some(popup) = queue.popup.take() {
+                    queue.sent_frames += 1;
+                    return Some(popup);
+                }
+
+                if queue.senders == 0 {
+                    return None;
+                }
+            }
+
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for SubscriberReceiver {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.receiver_alive = false;
+        queue.events.clear();
+        queue.audio.clear();
+        queue.frame = None;
+        queue.popup = None;
+    }
+}
+
+/// Adds the dirty rects of a newer frame to the pending ones. They collapse into
+/// their bounding box when there are too many or they cover most of it anyway.
+fn merge_dirty_rects(mut pending: Vec<DirtyRect>, dirty_rects: Vec<DirtyRect>) -> Vec<DirtyRect> {
+    pending.extend(dirty_rects);
+    let Some(first) = pending.first() else {
+        return pending;
+    };
+
+    let (mut left, mut top) = (first.x, first.y);
+    let (mut right, mut bottom) = (first.x + first.width, first.y + first.height);
+    for rect in &pending[1..] {
+        left = left.min(rect.x);
+        top = top.min(rect.y);
+        right = right.max(rect.x + rect.width);
+        bottom = bottom.max(rect.y + rect.height);
+    }
+    let bounds = DirtyRect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    };
+
+    let area = pending.iter().map(DirtyRect::area).sum::<usize>();
+    if pending.len() > MAX_DIRTY_RECTS || area >= bounds.area() * 3 / 4 {
+        vec![bounds]
+    } else {
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AudioPacket, AudioStream, DirtyRect, Framebuffer, PopupLayer, SharedFrame};
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> DirtyRect {
+        DirtyRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn frame(dirty_rects: Vec<DirtyRect>) -> TabMessage {
+        TabMessage::Frame {
+            framebuffer: Arc::new(Mutex::new(Framebuffer::default())),
+            dirty_rects,
+            sequence: 0,
+            timestamp: 0,
+        }
+    }
+
+    fn popup() -> TabMessage {
+        TabMessage::Popup(PopupLayer {
+            rect: rect(0, 0, 1, 1),
+            data: Arc::new(vec![0; 4]),
+            dpr: 1.0,
+            sequence: 0,
+            timestamp: 0,
+        })
+    }
+
+    fn shared_frame(dirty_rects: Vec<DirtyRect>) -> TabMessage {
+        TabMessage::FrameReady(SharedFrame {
+            path: String::new(),
+            sequence: 0,
+            slot: 0,
+            offset: 0,
+            width: 0,
+            height: 0,
+            dpr: 1.0,
+            dirty_rects,
+        })
+    }
+
+    fn audio_started() -> TabMessage {
+        TabMessage::AudioStarted(AudioStream {
+            sample_rate: 48000,
+            channels: 2,
+            channel_layout: "Stereo".into(),
+            frames_per_buffer: 480,
+        })
+    }
+
+    fn audio_packet(pts: i64) -> TabMessage {
+        TabMessage::AudioPacket(AudioPacket {
+            data: Arc::new(Vec::new()),
+            frames: 0,
+            channels: 2,
+            sample_rate: 48000,
+            pts,
+        })
+    }
+
+    fn audio_event(message: Option<TabMessage>) -> String {
+        match message {
+            Some(TabMessage::AudioStarted(_)) => "started".into(),
+            Some(TabMessage::AudioPacket(packet)) => format!("packet {}", packet.pts),
+            Some(TabMessage::AudioStopped) => "stopped".into(),
+            message => panic!("expected audio, got {:?}", message),
+        }
+    }
+
+    fn dirty_rects(message: Option<TabMessage>) -> Vec<DirtyRect> {
+        match message {
+            Some(TabMessage::Frame { dirty_rects, .. }) => dirty_rects,
+            message => panic!("expected a frame, got {:?}", message),
+        }
+    }
+
+    #[tokio::test]
+    async fn frames_are_coalesced() {
+        let (sender, mut receiver) = channel();
+        sender.send(frame(vec![rect(0, 0, 10, 10)])).unwrap();
+        sender.send(frame(vec![rect(20, 20, 10, 10)])).unwrap();
+
+        let stats = sender.stats();
+        assert_eq!(stats.queued_frames, 1);
+        assert_eq!(stats.dropped_frames, 1);
+
+        let received = dirty_rects(receiver.recv().await);
+        assert_eq!(received, [rect(0, 0, 10, 10), rect(20, 20, 10, 10)]);
+        assert_eq!(sender.stats().sent_frames, 1);
+        assert_eq!(sender.stats().queued_frames, 0);
+    }
+
+    #[tokio::test]
+    async fn events_are_kept_in_order() {
+        let (sender, mut receiver) = channel();
+        sender.send(TabMessage::Title("first".into())).unwrap();
+        sender.send(frame(vec![rect(0, 0, 10, 10)])).unwrap();
+        sender.send(TabMessage::Title("second".into())).unwrap();
+        assert_eq!(sender.stats().queued_events, 2);
+
+        let titles = [receiver.recv().await, receiver.recv().await];
+        assert!(matches!(&titles[0], Some(TabMessage::Title(t)) if t == "first"));
+        assert!(matches!(&titles[1], Some(TabMessage::Title(t)) if t == "second"));
+        assert_eq!(dirty_rects(receiver.recv().await), [rect(0, 0, 10, 10)]);
+    }
+
+    #[tokio::test]
+    async fn shared_frames_replace_frames() {
+        let (sender, mut receiver) = channel();
+        sender.send(shared_frame(vec![rect(0, 0, 10, 10)])).unwrap();
+        assert_eq!(sender.stats().queued_frames, 0);
+
+        sender.use_shared_frames(true);
+        sender.send(frame(vec![rect(0, 0, 10, 10)])).unwrap();
+        assert_eq!(sender.stats().queued_frames, 0);
+
+        sender.send(shared_frame(vec![rect(0, 0, 10, 10)])).unwrap();
+        sender
+            .send(shared_frame(vec![rect(20, 20, 10, 10)]))
+            .unwrap();
+        assert_eq!(sender.stats().dropped_frames, 1);
+        match receiver.recv().await {
+            Some(TabMessage::FrameReady(frame)) => {
+                assert_eq!(
+                    frame.dirty_rects,
+                    [rect(0, 0, 10, 10), rect(20, 20, 10, 10)]
+                )
+            }
+            message => panic!("expected a shared frame, got {:?}", message),
+        }
+    }
+
+    #[tokio::test]
+    async fn hiding_a_popup_drops_the_queued_one() {
+        let (sender, mut receiver) = channel();
+        sender.send(popup()).unwrap();
+        sender.send(popup()).unwrap();
+        assert_eq!(sender.stats().queued_frames, 1);
+        assert_eq!(sender.stats().dropped_frames, 1);
+
+        sender.send(TabMessage::PopupHidden).unwrap();
+        assert_eq!(sender.stats().queued_frames, 0);
+        assert!(matches!(
+            receiver.recv().await,
+            Some(TabMessage::PopupHidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn receiver_ends_once_senders_are_gone() {
+        let (sender, mut receiver) = channel();
+        let clone = sender.clone();
+        sender.send(TabMessage::Title("title".into())).unwrap();
+        drop(sender);
+        drop(clone);
+
+        assert!(matches!(receiver.recv().await, Some(TabMessage::Title(_))));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[test]
+    fn send_fails_without_receiver() {
+        let (sender, receiver) = channel();
+        drop(receiver);
+        assert!(sender.send(TabMessage::Title("title".into())).is_err());
+    }
+
+    #[test]
+    fn sparse_dirty_rects_are_kept() {
+        let merged = merge_dirty_rects(vec![rect(0, 0, 10, 10)], vec![rect(90, 90, 10, 10)]);
+        assert_eq!(merged, [rect(0, 0, 10, 10), rect(90, 90, 10, 10)]);
+        assert!(merge_dirty_rects(Vec::new(), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn dense_dirty_rects_collapse() {
+        let merged = merge_dirty_rects(vec![rect(0, 0, 10, 10)], vec![rect(10, 0, 10, 10)]);
+        assert_eq!(merged, [rect(0, 0, 20, 10)]);
+
+        let merged = merge_dirty_rects(
+            vec![rect(0, 0, 10, 10), rect(10, 0, 10, 10)],
+            vec![rect(0, 10, 10, 10)],
+        );
+        assert_eq!(merged, [rect(0, 0, 20, 20)]);
+    }
+
+    #[test]
+    fn too_many_dirty_rects_collapse() {
+        let pending = (0..MAX_DIRTY_RECTS as u32)
+            .map(|i| rect(i * 100, 0, 1, 1))
+            .collect();
+        let merged = merge_dirty_rects(pending, vec![rect(0, 100, 1, 1)]);
+        assert_eq!(
+            merged,
+            [rect(0, 0, (MAX_DIRTY_RECTS as u32 - 1) * 100 + 1, 101)]
+        );
+    }
+
+    #[tokio::test]
+    async fn audio_streams_keep_their_order() {
+        let (sender, mut receiver) = channel();
+        sender.enable_audio(true);
+        sender.send(audio_started()).unwrap();
+        sender.send(audio_packet(1)).unwrap();
+        sender.send(audio_packet(2)).unwrap();
+        sender.send(TabMessage::AudioStopped).unwrap();
+        sender.send(audio_started()).unwrap();
+        sender.send(audio_packet(3)).unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..6 {
+            events.push(audio_event(receiver.recv().await));
+        }
+        assert_eq!(
+            events,
+            ["started", "packet 1", "packet 2", "stopped", "started", "packet 3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn audio_without_packets() {
+        let (sender, mut receiver) = channel();
+        sender.send(audio_started()).unwrap();
+        sender.send(audio_packet(1)).unwrap();
+        sender.send(TabMessage::AudioStopped).unwrap();
+
+        assert_eq!(sender.stats().queued_events, 2);
+        assert_eq!(audio_event(receiver.recv().await), "started");
+        assert_eq!(audio_event(receiver.recv().await), "stopped");
+    }
+
+    #[tokio::test]
+    async fn only_audio_packets_are_dropped() {
+        let (sender, mut receiver) = channel();
+        sender.enable_audio(true);
+        sender.send(audio_started()).unwrap();
+        for pts in 0..MAX_QUEUED_AUDIO_PACKETS as i64 {
+            sender.send(audio_packet(pts)).unwrap();
+        }
+        assert_eq!(sender.stats().dropped_audio_packets, 1);
+
+        assert_eq!(audio_event(receiver.recv().await), "started");
+        assert_eq!(audio_event(receiver.recv().await), "packet 1");
+    }
+}
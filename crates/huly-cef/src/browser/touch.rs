@@ -0,0 +1,56 @@
+use crate::{state::SharedBrowserState, TouchPoint, TouchType};
+use cef_ui::{Browser, PointerType, TouchEvent, TouchEventType};
+use log::error;
+
+pub struct Touch {
+    inner: Browser,
+    state: SharedBrowserState,
+}
+
+impl Clone for Touch {
+    fn clone(&self) -> Self {
+        Touch {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl Touch {
+    pub fn new(inner: Browser, state: SharedBrowserState) -> Self {
+        Touch { inner, state }
+    }
+
+    /// Sends a touch point. Multi-touch gestures send one event per finger,
+    /// telling the fingers apart by [`TouchPoint::id`].
+    pub fn send(&self, point: &TouchPoint) {
+        let modifiers = self.state.read(|s| s.input.get_event_flags());
+        let event_type = match point.touch_type {
+            TouchType::Pressed => TouchEventType::Pressed,
+            TouchType::Moved => TouchEventType::Moved,
+            TouchType::Released => TouchEventType::Released,
+            TouchType::Cancelled => TouchEventType::Cancelled,
+        };
+
+        let event = TouchEvent {
+            id: point.id,
+            x: point.x,
+            y: point.y,
+            radius_x: point.radius_x,
+            radius_y: point.radius_y,
+            rotation_angle: point.rotation_angle,
+            pressure: point.pressure,
+            event_type,
+            modifiers,
+            pointer_type: PointerType::Touch,
+        };
+
+        let Ok(host) = self.inner.get_host() else {
+            error!("failed to send touch event: browser host is gone");
+            return;
+        };
+        if let Err(e) = host.send_touch_event(&event) {
+            error!("failed to send touch event: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use cef_ui::Browser;
+
+use crate::{browser::preferences::origin, state::SharedBrowserState, TabMessage, ZoomChanged};
+
+/// Zoom factors offered by zoom in and zoom out, the same as in Chrome.
+const ZOOM_FACTORS: [f64; 17] = [
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0,
+];
+
+/// Zooms the page. Zoom levels are logarithmic: level 0 is 100%, each step of 1
+/// scales the page by 1.2. The level is remembered for the origin of the page.
+pub struct Zoom {
+    inner: Browser,
+    state: SharedBrowserState,
+}
+
+impl Clone for Zoom {
+    fn clone(&self) -> Self {
+        Zoom {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl Zoom {
+    pub fn new(inner: Browser, state: SharedBrowserState) -> Self {
+        Zoom { inner, state }
+    }
+
+    pub fn level(&self) -> f64 {
+        self.state.read(|s| s.zoom_level)
+    }
+
+    pub fn factor(&self) -> f64 {
+        zoom_factor(self.level())
+    }
+
+    pub fn set_level(&self, level: f64) {
+        set_level(&self.inner, &self.state, level);
+    }
+
+    pub fn zoom_in(&self) {
+        zoom_in(&self.inner, &self.state);
+    }
+
+    pub fn zoom_out(&self) {
+        zoom_out(&self.inner, &self.state);
+    }
+
+    pub fn reset(&self) {
+        self.set_level(0.0);
+    }
+}
+
+fn zoom_factor(level: f64) -> f64 {
+    1.2f64.powf(level)
+}
+
+fn zoom_level(factor: f64) -> f64 {
+    factor.ln() / 1.2f64.ln()
+}
+
+pub(crate) fn zoom_in(browser: &Browser, state: &SharedBrowserState) {
+    let factor = zoom_factor(state.read(|s| s.zoom_level));
+    if let Some(next) = ZOOM_FACTORS.iter().find(|f| **f > factor + 0.001) {
+        set_level(browser, state, zoom_level(*next));
+    }
+}
+
+pub(crate) fn zoom_out(browser: &Browser, state: &SharedBrowserState) {
+    let factor = zoom_factor(state.read(|s| s.zoom_level));
+    if let Some(next) = ZOOM_FACTORS.iter().rev().find(|f| **f < factor - 0.001) {
+        set_level(browser, state, zoom_level(*next));
+    }
+}
+
+/// Zooms the page and remembers the level for its origin.
+pub(crate) fn set_level(browser: &Browser, state: &SharedBrowserState, level: f64) {
+    let level = level.clamp(
+        zoom_level(ZOOM_FACTORS[0]),
+        zoom_level(ZOOM_FACTORS[ZOOM_FACTORS.len() - 1]),
+    );
+    apply_level(browser, state, level);
+
+    let (origin, preferences) = state.read(|s| (s.zoom_origin.clone(), s.preferences.clone()));
+    if let Some(origin) = origin {
+        preferences.set_zoom_level(&origin, level);
+    }
+}
+
+/// Restores the level remembered for the origin of `url` once the page has
+/// navigated to another origin.
+pub(crate) fn restore_level(browser: &Browser, state: &SharedBrowserState, url: &str) {
+    let origin = origin(url);
+    let (changed, preferences) = state.update_and_return(|s| {
+        let changed = s.zoom_origin != origin;
+        s.zoom_origin = origin.clone();
+        (changed, s.preferences.clone())
+    });
+
+    if changed {
+        let level = origin.map_or(0.0, |origin| preferences.zoom_level(&origin));
+        apply_level(browser, state, level);
+    }
+}
+
+fn apply_level(browser: &Browser, state: &SharedBrowserState, level: f64) {
+    if let Ok(host) = browser.get_host() {
+        _ = host.set_zoom_level(level);
+    }
+
+    let changed = state.update_and_return(|s| std::mem::replace(&mut s.zoom_level, level) != level);
+    if changed {
+        state.notify(TabMessage::ZoomChanged(ZoomChanged {
+            level,
+            factor: zoom_factor(level),
+        }));
+    }
+}
@@ -292,3 +292,147 @@ function isElementClicked(selector) {
     return false;
 }
 "#;
+
+/// Name of the process message the browser process sends the content of the next
+/// paste with, an empty string clears it.
+pub const SET_CLIPBOARD_MESSAGE: &str = "huly_set_clipboard";
+/// Name of the process message the render process sends content copied by the page
+/// with. Unlike the messages of `sendMessage`, page scripts can't send it.
+pub const CLIPBOARD_MESSAGE: &str = "huly_clipboard";
+
+/// Bridges the page clipboard to the client. The script evaluates to a function that
+/// the render process calls with a native function sending copied content to the
+/// browser process. It returns the function the render process sets the content
+/// of the next paste with, so that neither is reachable from page scripts.
+pub const CLIPBOARD_BRIDGE: &str = r#"
+(function (sendClipboard) {
+    let pasted = null;
+
+    function selectedImage(range) {
+        const node = range.startContainer.childNodes[range.startOffset];
+        if (range.endContainer === range.startContainer && range.endOffset === range.startOffset + 1 && node instanceof HTMLImageElement) {
+            return node;
+        }
+        return null;
+    }
+
+    function imageData(image) {
+        try {
+            const canvas = document.createElement('canvas');
+            canvas.width = image.naturalWidth;
+            canvas.height = image.naturalHeight;
+            canvas.getContext('2d').drawImage(image, 0, 0);
+            return canvas.toDataURL('image/png').split(',')[1];
+        } catch (e) {
+            return null;
+        }
+    }
+
+    function onCopy(event) {
+        if (!event.isTrusted) {
+            return;
+        }
+
+        let clipboard = { text: null, html: null, image: null };
+        const element = document.activeElement;
+
+        if (event.defaultPrevented && event.clipboardData) {
+            clipboard.text = event.clipboardData.getData('text/plain') || null;
+            clipboard.html = event.clipboardData.getData('text/html') || null;
+        } else if ((element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement) && element.selectionStart !== null) {
+            clipboard.text = element.value.substring(element.selectionStart, element.selectionEnd) || null;
+        } else {
+            const selection = window.getSelection();
+            if (!selection || selection.isCollapsed) {
+                return;
+            }
+
+            const container = document.createElement('div');
+            for (let i = 0; i < selection.rangeCount; i++) {
+                container.appendChild(selection.getRangeAt(i).cloneContents());
+            }
+            clipboard.text = selection.toString() || null;
+            clipboard.html = container.innerHTML || null;
+
+            const image = selectedImage(selection.getRangeAt(0));
+            if (image) {
+                clipboard.image = imageData(image);
+            }
+        }
+
+        if (!clipboard.text && !clipboard.html && !clipboard.image) {
+            return;
+        }
+
+        pasted = clipboard;
+        sendClipboard(JSON.stringify(clipboard));
+    }
+
+    function onPaste(event) {
+        const clipboard = pasted;
+        if (!event.isTrusted || !clipboard) {
+            return;
+        }
+        event.preventDefault();
+        event.stopImmediatePropagation();
+
+        const data = new DataTransfer();
+        if (clipboard.text) {
+            data.setData('text/plain', clipboard.text);
+        }
+        if (clipboard.html) {
+            data.setData('text/html', clipboard.html);
+        }
+        if (clipboard.image) {
+            const bytes = Uint8Array.from(atob(clipboard.image), c => c.charCodeAt(0));
+            data.items.add(new File([bytes], 'image.png', { type: 'image/png' }));
+        }
+
+        const paste = new ClipboardEvent('paste', { clipboardData: data, bubbles: true, cancelable: true });
+        if (!event.target.dispatchEvent(paste)) {
+            return;
+        }
+
+        const element = document.activeElement;
+        const richText = element && element.isContentEditable;
+        if (richText && clipboard.html) {
+            document.execCommand('insertHTML', false, clipboard.html);
+        } else if (clipboard.text) {
+            document.execCommand('insertText', false, clipboard.text);
+        } else if (richText && clipboard.image) {
+            document.execCommand('insertImage', false, 'data:image/png;base64,' + clipboard.image);
+        }
+    }
+
+    window.addEventListener('copy', onCopy);
+    window.addEventListener('cut', onCopy);
+    window.addEventListener('paste', onPaste, true);
+
+    return function (json) {
+        pasted = json ? JSON.parse(json) : null;
+    };
+})
+"#;
+
+/// Zooms the page on Ctrl+wheel unless the page handles the wheel event itself,
+/// e.g. to zoom a map. Sent with the reserved `__huly_zoom` message id.
+pub const CTRL_WHEEL_ZOOM: &str = r#"
+(function () {
+    const ZOOM_INTERVAL = 100;
+    let lastZoom = 0;
+
+    window.addEventListener('wheel', function (event) {
+        if (!event.isTrusted || !event.ctrlKey || event.deltaY === 0) {
+            return;
+        }
+
+        setTimeout(function () {
+            if (event.defaultPrevented || event.timeStamp - lastZoom < ZOOM_INTERVAL) {
+                return;
+            }
+            lastZoom = event.timeStamp;
+            sendMessage({ id: '__huly_zoom', message: event.deltaY < 0 ? 'in' : 'out' });
+        }, 0);
+    });
+})();
+"#;
@@ -6,7 +6,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 
-#[derive(Debug, Serialize_repr, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq)]
 #[repr(u8)]
 pub enum MouseButton {
     Left = 0,
@@ -14,6 +14,63 @@ pub enum MouseButton {
     Right = 2,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TouchType {
+    Pressed,
+    Moved,
+    Released,
+    Cancelled,
+}
+
+/// Content of the clipboard bridge. `image` is a base64 encoded PNG.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipboardData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+/// A file dropped onto the page from the client's machine, `data` is base64 encoded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DroppedFile {
+    pub name: String,
+    pub data: String,
+}
+
+/// Data dragged onto the page from outside of the browser.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DropData {
+    #[serde(default)]
+    pub files: Vec<DroppedFile>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub url: Option<String>,
+}
+
+/// A single touch point. `id` identifies the finger across its pressed, moved and
+/// released events, coordinates and radii are in CSS pixels.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TouchPoint {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub touch_type: TouchType,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub radius_x: f32,
+    #[serde(default)]
+    pub radius_y: f32,
+    #[serde(default)]
+    pub rotation_angle: f32,
+    /// Between 0 and 1, 0 if the device doesn't report pressure.
+    #[serde(default)]
+    pub pressure: f32,
+}
+
 #[derive(Debug, Clone, Serialize_repr, Hash, PartialEq, Eq)]
 #[repr(u8)]
 pub enum LoadStatus {
@@ -51,6 +108,152 @@ pub struct ClickableElement {
     pub text: String,
 }
 
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl ScreenshotFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Jpeg => "jpeg",
+            ScreenshotFormat::Webp => "webp",
+        }
+    }
+}
+
+/// How a screenshot is scaled to the requested `width` and `height`.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotFit {
+    /// Stretch to exactly the requested size.
+    #[default]
+    Exact,
+    /// Scale down or up to fit the requested size, keeping the aspect ratio.
+    Contain,
+    /// Keep the captured size.
+    None,
+}
+
+/// A rectangle in CSS pixels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScreenshotClip {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ScreenshotOptions {
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Region of the page to capture, the viewport by default.
+    #[serde(default)]
+    pub clip: Option<ScreenshotClip>,
+    /// Capture the whole scrollable page instead of the viewport.
+    #[serde(default)]
+    pub full_page: bool,
+    #[serde(default)]
+    pub format: ScreenshotFormat,
+    /// Quality for lossy formats, from 0 to 100.
+    #[serde(default)]
+    pub quality: Option<u8>,
+    #[serde(default)]
+    pub fit: ScreenshotFit,
+    /// Encode the tab's framebuffer instead of capturing through DevTools.
+    /// `clip` is then relative to the viewport and `full_page` isn't supported.
+    #[serde(default)]
+    pub fast: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// Device metrics of an emulated viewport, in CSS pixels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EmulatedViewport {
+    pub width: u32,
+    pub height: u32,
+    /// Device pixel ratio, 0 keeps the tab's own.
+    #[serde(default)]
+    pub device_scale_factor: f64,
+    /// Emulate a mobile device: meta viewport, overlay scrollbars and text autosizing.
+    #[serde(default)]
+    pub mobile: bool,
+}
+
+/// Device and media emulation of a tab. Unset fields keep the browser defaults.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmulationSettings {
+    #[serde(default)]
+    pub viewport: Option<EmulatedViewport>,
+    #[serde(default)]
+    pub touch: bool,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Value of the `prefers-color-scheme` media feature.
+    #[serde(default)]
+    pub color_scheme: Option<ColorScheme>,
+    /// Match `prefers-reduced-motion: reduce`.
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// Apply `@media print` styles.
+    #[serde(default)]
+    pub print_media: bool,
+}
+
+/// Options of `Page.printToPDF`, sizes are in inches.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct PdfOptions {
+    #[serde(default)]
+    pub landscape: bool,
+    #[serde(default)]
+    pub print_background: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paper_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paper_height: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_top: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_bottom: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_left: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_right: Option<f64>,
+    /// Pages to print, e.g. `1-5, 8`. All pages when empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub page_ranges: String,
+}
+
+/// A rectangle in framebuffer pixels.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DirtyRect {
+    pub fn area(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Framebuffer {
     pub width: u32,
@@ -59,6 +262,126 @@ pub struct Framebuffer {
     pub data: Vec<u8>,
 }
 
+/// A frame written into the shared-memory ring at `path`, see
+/// [`crate::browser::shared_frames::SHARED_FRAMES_VERSION`] for the file layout.
+/// The RGBA pixels are at `offset`, `width * height * 4` bytes long.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedFrame {
+    pub path: String,
+    pub sequence: u64,
+    pub slot: u32,
+    pub offset: u64,
+    pub width: u32,
+    pub height: u32,
+    pub dpr: f64,
+    pub dirty_rects: Vec<DirtyRect>,
+}
+
+/// A cursor image set by the page, e.g. with `cursor: url(...)`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomCursor {
+    /// Identifies the image, clients cache images by it.
+    pub hash: String,
+    /// Base64 encoded PNG. The tab socket omits it when the image has already been
+    /// sent over the same connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<Arc<String>>,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32,
+    /// Device pixels per CSS pixel of the image.
+    pub scale: f32,
+}
+
+/// A range of characters in an editable element, `to` is exclusive.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TextRange {
+    pub from: u32,
+    pub to: u32,
+}
+
+/// A rectangle in CSS pixels, relative to the view.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ViewRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// The IME composition range has changed. Clients place their candidate window
+/// next to `character_bounds`, which holds the bounds of every composed character.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImeComposition {
+    pub range: TextRange,
+    pub character_bounds: Vec<ViewRect>,
+}
+
+/// Progress of a search started with `find`. `active_match` is the 1-based index
+/// of the highlighted match and `selection_rect` its position.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindResult {
+    pub identifier: i32,
+    pub count: i32,
+    pub active_match: i32,
+    pub selection_rect: ViewRect,
+    /// Whether the search is complete, earlier results may have a partial count.
+    pub final_update: bool,
+}
+
+/// A page asks for permissions, e.g. `camera`, `microphone`, `geolocation` or
+/// `notifications`. Clients answer with `respondPermission`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionRequest {
+    pub id: u64,
+    pub origin: String,
+    pub permissions: Vec<String>,
+}
+
+/// Zoom level of the page, `factor` is `1.2^level`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoomChanged {
+    pub level: f64,
+    pub factor: f64,
+}
+
+/// Format of the audio a tab plays, sent when its audio stream starts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStream {
+    pub sample_rate: i32,
+    pub channels: i32,
+    /// CEF channel layout, e.g. `Stereo` or `Surround51`.
+    pub channel_layout: String,
+    pub frames_per_buffer: i32,
+}
+
+/// A packet of audio played by the tab. `data` holds `frames * channels` interleaved
+/// samples between -1 and 1, `pts` is the presentation timestamp in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioPacket {
+    pub data: Arc<Vec<f32>>,
+    pub frames: u32,
+    pub channels: u8,
+    pub sample_rate: u32,
+    pub pts: i64,
+}
+
+/// Pixels of a popup widget. `rect` is its position in framebuffer pixels and
+/// `data` holds `rect.width * rect.height` RGBA pixels.
+#[derive(Debug, Clone, Serialize)]
+pub struct PopupLayer {
+    pub rect: DirtyRect,
+    pub data: Arc<Vec<u8>>,
+    pub dpr: f64,
+    /// Counts popup paints separately from frames.
+    pub sequence: u64,
+    pub timestamp: u64,
+}
+
 impl Framebuffer {
     pub fn length_in_bytes(width: u32, height: u32, dpr: f64) -> usize {
         (width as f64 * dpr * height as f64 * dpr * 4.0) as usize
@@ -68,7 +391,11 @@ impl Framebuffer {
 #[derive(Hash, PartialEq, Eq)]
 pub enum TabMessageType {
     Frame,
+    FrameReady,
+    Popup,
+    PopupHidden,
     Cursor,
+    CustomCursor,
     Title,
     Url,
     Favicon,
@@ -79,14 +406,107 @@ pub enum TabMessageType {
     ExternalLink,
     DownloadProgress,
     FileDialog,
+    ImeComposition,
+    Clipboard,
+    FindResult,
+    ZoomChanged,
+    AudioStarted,
+    AudioPacket,
+    AudioStopped,
+    Audible,
+    AudioMuted,
+    PermissionRequest,
+    PermissionDismissed,
 }
 
+/// Magic value at the start of every binary frame message.
+pub const FRAME_MAGIC: [u8; 4] = *b"HFRM";
+
+/// Version of the binary frame format. [`TabMessage::Frame`] and [`TabMessage::Popup`]
+/// are sent over the tab socket as binary messages, all other messages as JSON except
+/// for [`TabMessage::AudioPacket`], see [`AUDIO_FORMAT_VERSION`].
+///
+/// Every binary message has the following layout (all numbers are little-endian):
+///
+/// | offset | size | field                                                    |
+/// |--------|------|----------------------------------------------------------|
+/// | 0      | 4    | magic, `HFRM`                                            |
+/// | 4      | 1    | version                                                  |
+/// | 5      | 1    | kind (0 = keyframe, 1 = delta, 2 = popup)                |
+/// | 6      | 1    | encoding (0 = raw, 1 = png, 2 = jpeg, 3 = webp, 4 = qoi) |
+/// | 7      | 1    | pixel format of the decoded rects (0 = RGBA8)            |
+/// | 8      | 8    | sequence (u64)                                           |
+/// | 16     | 8    | paint timestamp, microseconds since the UNIX epoch       |
+/// | 24     | 4    | device pixel ratio (f32)                                 |
+/// | 28     | 4    | frame or popup width                                     |
+/// | 32     | 4    | frame or popup height                                    |
+/// | 36     | 4    | rect count                                               |
+/// | 40     | ...  | rects                                                    |
+///
+/// Each rect is `x`, `y`, `width`, `height` and the payload length as u32, followed by
+/// the payload: raw pixels or an image in the encoding negotiated for the tab socket.
+/// A keyframe always carries a single rect covering the whole frame. A delta carries the
+/// dirty rects, it applies on top of the previous frame. Frames and popups have separate
+/// sequences; a gap in the frame sequence means that frames were merged because the
+/// client didn't keep up, their dirty rects are then merged too.
+///
+/// A popup is a separate layer drawn on top of the frame, its single rect is the
+/// popup position in the frame.
+pub const FRAME_FORMAT_VERSION: u8 = 4;
+
+pub const FRAME_HEADER_SIZE: usize = 40;
+pub const FRAME_RECT_HEADER_SIZE: usize = 20;
+pub const PIXEL_FORMAT_RGBA8: u8 = 0;
+
+/// Magic value at the start of every binary audio message.
+pub const AUDIO_MAGIC: [u8; 4] = *b"HAUD";
+
+/// Version of the binary audio format. [`TabMessage::AudioPacket`] is sent over the
+/// tab socket as a binary message, only to clients that asked for audio.
+///
+/// | offset | size | field                                              |
+/// |--------|------|----------------------------------------------------|
+/// | 0      | 4    | magic, `HAUD`                                      |
+/// | 4      | 1    | version                                            |
+/// | 5      | 1    | sample format (0 = f32, 1 = s16, 2 = Opus)         |
+/// | 6      | 1    | channel count                                      |
+/// | 7      | 1    | reserved                                           |
+/// | 8      | 4    | sample rate                                        |
+/// | 12     | 4    | frame count                                        |
+/// | 16     | 8    | presentation timestamp in milliseconds (i64)       |
+/// | 24     | ...  | `frame count * channel count` interleaved samples  |
+///
+/// An Opus message carries 20 ms Opus packets instead of samples, each one prefixed
+/// with its length as u16. Its frame count is the number of decoded frames and its
+/// timestamp the one of the first packet.
+pub const AUDIO_FORMAT_VERSION: u8 = 1;
+
+pub const AUDIO_HEADER_SIZE: usize = 24;
+
 /// Represents different types of events that can be sent from CEF browser
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type", content = "data")]
 pub enum TabMessage {
-    Frame(Arc<Mutex<Framebuffer>>),
+    /// The framebuffer has been repainted. `dirty_rects` lists the regions that changed
+    /// since the previous frame, in framebuffer pixels. `sequence` grows by one with
+    /// every paint and `timestamp` is the paint time in microseconds since the UNIX epoch.
+    Frame {
+        framebuffer: Arc<Mutex<Framebuffer>>,
+        dirty_rects: Vec<DirtyRect>,
+        sequence: u64,
+        timestamp: u64,
+    },
+    /// A frame has been written into the shared-memory ring. Only subscribers using
+    /// shared frames receive it, instead of [`TabMessage::Frame`].
+    FrameReady(SharedFrame),
+    /// A popup widget, e.g. a `<select>` dropdown, has been painted. It is kept
+    /// as a separate layer on top of the framebuffer.
+    Popup(PopupLayer),
+    /// The popup has been hidden, the framebuffer underneath is intact.
+    PopupHidden,
     Cursor(String),
+    /// Sent after `Cursor("Custom")` with the cursor image.
+    CustomCursor(CustomCursor),
     Title(String),
     Url(String),
     Favicon(String),
@@ -111,13 +531,34 @@ pub enum TabMessage {
         accept_extensions: Vec<String>,
         accept_descriptions: Vec<String>,
     },
+    ImeComposition(ImeComposition),
+    /// The page has copied or cut content. The server has no clipboard of its own,
+    /// so clients put it on their system clipboard.
+    Clipboard(ClipboardData),
+    FindResult(FindResult),
+    ZoomChanged(ZoomChanged),
+    /// The tab has started playing audio, [`TabMessage::AudioPacket`]s follow.
+    AudioStarted(AudioStream),
+    AudioPacket(AudioPacket),
+    /// The audio stream has stopped, a new one may start with a different format.
+    AudioStopped,
+    /// The tab has started or stopped producing sound.
+    Audible(bool),
+    AudioMuted(bool),
+    PermissionRequest(PermissionRequest),
+    /// The request has been answered, by this or another client, or dismissed by the page.
+    PermissionDismissed(u64),
 }
 
 impl TabMessage {
     pub fn event_type(&self) -> TabMessageType {
         match self {
-            TabMessage::Frame(_) => TabMessageType::Frame,
+            TabMessage::Frame { .. } => TabMessageType::Frame,
+            TabMessage::FrameReady(_) => TabMessageType::FrameReady,
+            TabMessage::Popup(_) => TabMessageType::Popup,
+            TabMessage::PopupHidden => TabMessageType::PopupHidden,
             TabMessage::Cursor(_) => TabMessageType::Cursor,
+            TabMessage::CustomCursor(_) => TabMessageType::CustomCursor,
             TabMessage::Title(_) => TabMessageType::Title,
             TabMessage::Url(_) => TabMessageType::Url,
             TabMessage::Favicon(_) => TabMessageType::Favicon,
@@ -128,6 +569,17 @@ impl TabMessage {
             TabMessage::ExternalLink(_) => TabMessageType::ExternalLink,
             TabMessage::DownloadProgress { .. } => TabMessageType::DownloadProgress,
             TabMessage::FileDialog { .. } => TabMessageType::FileDialog,
+            TabMessage::ImeComposition(_) => TabMessageType::ImeComposition,
+            TabMessage::Clipboard(_) => TabMessageType::Clipboard,
+            TabMessage::FindResult(_) => TabMessageType::FindResult,
+            TabMessage::ZoomChanged(_) => TabMessageType::ZoomChanged,
+            TabMessage::AudioStarted(_) => TabMessageType::AudioStarted,
+            TabMessage::AudioPacket(_) => TabMessageType::AudioPacket,
+            TabMessage::AudioStopped => TabMessageType::AudioStopped,
+            TabMessage::Audible(_) => TabMessageType::Audible,
+            TabMessage::AudioMuted(_) => TabMessageType::AudioMuted,
+            TabMessage::PermissionRequest(_) => TabMessageType::PermissionRequest,
+            TabMessage::PermissionDismissed(_) => TabMessageType::PermissionDismissed,
         }
     }
 }
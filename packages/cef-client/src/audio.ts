@@ -0,0 +1,64 @@
+import { AudioPacket } from "./types.js";
+
+// See AUDIO_FORMAT_VERSION in crates/huly-cef/src/messages.rs for the layout.
+const AUDIO_MAGIC = 0x44554148; // "HAUD"
+const AUDIO_FORMAT_VERSION = 1;
+const AUDIO_HEADER_SIZE = 24;
+
+enum SampleFormat {
+    F32 = 0,
+    S16 = 1,
+    Opus = 2,
+}
+
+export function isAudioMessage(buffer: ArrayBuffer): boolean {
+    return buffer.byteLength >= 4 && new DataView(buffer).getUint32(0, true) === AUDIO_MAGIC;
+}
+
+export function decodeAudio(buffer: ArrayBuffer): AudioPacket | null {
+    let view = new DataView(buffer);
+    if (buffer.byteLength < AUDIO_HEADER_SIZE || view.getUint32(0, true) !== AUDIO_MAGIC) {
+        console.warn("Not an audio message");
+        return null;
+    }
+
+    let version = view.getUint8(4);
+    if (version !== AUDIO_FORMAT_VERSION) {
+        console.warn(`Unsupported audio format version: ${version}`);
+        return null;
+    }
+
+    let format = view.getUint8(5);
+    let channels = view.getUint8(6);
+    let sampleRate = view.getUint32(8, true);
+    let frames = view.getUint32(12, true);
+    let pts = Number(view.getBigInt64(16, true));
+
+    if (format === SampleFormat.Opus) {
+        let opus: Uint8Array[] = [];
+        let offset = AUDIO_HEADER_SIZE;
+        while (offset + 2 <= buffer.byteLength) {
+            let size = view.getUint16(offset, true);
+            opus.push(new Uint8Array(buffer, offset + 2, size));
+            offset += 2 + size;
+        }
+        return { data: new Float32Array(0), opus, frames, channels, sampleRate, pts };
+    }
+
+    let data = new Float32Array(frames * channels);
+    for (let i = 0; i < data.length; i++) {
+        switch (format) {
+            case SampleFormat.F32:
+                data[i] = view.getFloat32(AUDIO_HEADER_SIZE + i * 4, true);
+                break;
+            case SampleFormat.S16:
+                data[i] = view.getInt16(AUDIO_HEADER_SIZE + i * 2, true) / 0x7fff;
+                break;
+            default:
+                console.warn(`Unsupported audio sample format: ${format}`);
+                return null;
+        }
+    }
+
+    return { data, frames, channels, sampleRate, pts };
+}
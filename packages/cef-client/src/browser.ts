@@ -26,7 +26,11 @@ export class Browser {
         const params = {
             url: (options && options.url !== "") ? options.url : getConfig().defaultUrl,
             wait_until_loaded: options?.wait_until_loaded ?? false,
-            dpr: (typeof window !== 'undefined' ? window.devicePixelRatio : 1.0) || 1.0
+            dpr: (typeof window !== 'undefined' ? window.devicePixelRatio : 1.0) || 1.0,
+            frame_rate: options?.frame_rate ?? 60,
+            adaptive_frame_rate: options?.adaptive_frame_rate ?? false,
+            width: options?.width !== undefined ? Math.floor(options.width) : undefined,
+            height: options?.height !== undefined ? Math.floor(options.height) : undefined,
         };
 
         const result = await this.messageHandler.send('openTab', params);
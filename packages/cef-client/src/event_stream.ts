@@ -1,4 +1,6 @@
-import { Cursor, DownloadProgress, FileDialog, Frame, LoadState } from "./types.js";
+import { decodeAudio, isAudioMessage } from "./audio.js";
+import { FrameDecoder } from "./frame.js";
+import { AudioPacket, AudioStream, ClipboardData, Cursor, CustomCursor, DownloadProgress, FileDialog, Frame, FindResult, ImeComposition, LoadState, PermissionRequest, Popup, SharedFrame, Zoom } from "./types.js";
 
 type TabEvent = {
     Title: string;
@@ -6,12 +8,35 @@ type TabEvent = {
     LoadState: LoadState;
     Favicon: string;
     Cursor: Cursor;
+    CustomCursor: CustomCursor;
     UrlHovered: string;
     NewTab: string;
     ExternalLink: string;
     Frame: Frame;
+    FrameReady: SharedFrame;
+    // Popups such as <select> dropdowns are a separate layer drawn on top of the frame.
+    Popup: Popup;
+    PopupHidden: undefined;
     DownloadProgress: DownloadProgress;
     FileDialog: FileDialog;
+    ImeComposition: ImeComposition;
+    // Content copied or cut by the page, to be put on the client's clipboard.
+    Clipboard: ClipboardData;
+    FindResult: FindResult;
+    // Sent for zoom changes from the client, from Ctrl+wheel and when another origin
+    // restores its remembered zoom.
+    ZoomChanged: Zoom;
+    AudioStarted: AudioStream;
+    // Only sent to streams opened with the audio option.
+    AudioPacket: AudioPacket;
+    AudioStopped: undefined;
+    // Whether the tab produces sound, e.g. to show a speaker icon.
+    Audible: boolean;
+    AudioMuted: boolean;
+    // Answer with Tab.respondPermission, the prompt is gone once PermissionDismissed
+    // arrives with its id.
+    PermissionRequest: PermissionRequest;
+    PermissionDismissed: number;
 }
 
 interface Message<T extends keyof TabEvent> {
@@ -24,6 +49,13 @@ export class TabEventStream {
 
     subscribers: Map<keyof TabEvent, (data: any) => void> = new Map();
 
+    private decoder: FrameDecoder = new FrameDecoder(() => this.requestKeyframe());
+    // Frames are decoded asynchronously, all messages are emitted through this chain
+    // so that e.g. PopupHidden can't overtake the Popup sent before it.
+    private queue: Promise<void> = Promise.resolve();
+    // The server sends each cursor image once per connection.
+    private cursorImages: Map<string, string> = new Map();
+
     constructor(url: string) {
         this.websocket = new WebSocket(url);
         this.websocket.binaryType = "arraybuffer";
@@ -34,6 +66,12 @@ export class TabEventStream {
         this.websocket.close();
     }
 
+    requestKeyframe() {
+        if (this.websocket.readyState === WebSocket.OPEN) {
+            this.websocket.send(JSON.stringify({ type: "Keyframe" }));
+        }
+    }
+
     on<K extends keyof TabEvent>(eventType: K, callback: (data: TabEvent[K]) => void) {
         if (!this.subscribers.has(eventType)) {
             this.subscribers.set(eventType, callback);
@@ -43,28 +81,42 @@ export class TabEventStream {
     private onmessage(event: MessageEvent) {
         if (typeof event.data === "string") {
             let message: Message<keyof TabEvent> = JSON.parse(event.data);
-            this.emit(message.type, message.data);
+            if (message.type === "CustomCursor") {
+                let cursor = message.data as CustomCursor;
+                if (cursor.image) {
+                    this.cursorImages.set(cursor.hash, cursor.image);
+                } else {
+                    cursor.image = this.cursorImages.get(cursor.hash) ?? "";
+                }
+            }
+            this.enqueue(() => this.emit(message.type, message.data));
         }
 
-        if (event.data instanceof ArrayBuffer) {
-            let view = new DataView(event.data);
-            let width = view.getUint32(0, true);
-            let height = view.getUint32(4, true);
-            let data = new Uint8Array(event.data, 8);
+        if (event.data instanceof ArrayBuffer && isAudioMessage(event.data)) {
+            let packet = decodeAudio(event.data);
+            if (packet) {
+                this.enqueue(() => this.emit("AudioPacket", packet));
+            }
+            return;
+        }
 
-            let message: Message<keyof TabEvent> = {
-                type: "Frame",
-                data: {
-                    width,
-                    height,
-                    data
+        if (event.data instanceof ArrayBuffer) {
+            let data = event.data;
+            this.enqueue(async () => {
+                let decoded = await this.decoder.apply(data);
+                if (decoded) {
+                    this.emit(decoded.type, decoded.data);
                 }
-            };
-
-            this.emit(message.type, message.data);
+            });
         }
     }
 
+    private enqueue(handler: () => void | Promise<void>) {
+        this.queue = this.queue
+            .then(handler)
+            .catch((error) => console.error("Failed to handle a tab message:", error));
+    }
+
     private emit<K extends keyof TabEvent>(type: K, data: TabEvent[K]) {
         let callback = this.subscribers.get(type);
         if (callback) {
@@ -0,0 +1,210 @@
+import { AudioFormat, Frame, FrameEncoding, FrameHeader, FrameTransport, Popup, Rect } from "./types.js";
+
+// See FRAME_FORMAT_VERSION in crates/huly-cef/src/messages.rs for the layout.
+const FRAME_MAGIC = 0x4d524648; // "HFRM"
+const FRAME_FORMAT_VERSION = 4;
+const FRAME_HEADER_SIZE = 40;
+const RECT_HEADER_SIZE = 20;
+
+enum FrameKind {
+    Key = 0,
+    Delta = 1,
+    Popup = 2,
+}
+
+export type DecodedFrame = { type: "Frame", data: Frame } | { type: "Popup", data: Popup };
+
+enum EncodingId {
+    Raw = 0,
+    Png = 1,
+    Jpeg = 2,
+    WebP = 3,
+    Qoi = 4,
+}
+
+const MIME_TYPES: { [id: number]: string } = {
+    [EncodingId.Png]: "image/png",
+    [EncodingId.Jpeg]: "image/jpeg",
+    [EncodingId.WebP]: "image/webp",
+};
+
+export function frameQuery(encoding?: FrameEncoding, quality?: number, transport?: FrameTransport, audio?: AudioFormat): string {
+    let params = new URLSearchParams();
+    if (transport) {
+        params.set("transport", transport);
+    }
+    if (encoding) {
+        params.set("encoding", encoding);
+    }
+    if (quality !== undefined) {
+        params.set("quality", Math.round(quality).toString());
+    }
+    if (audio) {
+        params.set("audio", audio);
+    }
+    let query = params.toString();
+    return query ? "?" + query : "";
+}
+
+export function parseFrameHeader(buffer: ArrayBuffer): FrameHeader | null {
+    let view = new DataView(buffer);
+    if (buffer.byteLength < FRAME_HEADER_SIZE || view.getUint32(0, true) !== FRAME_MAGIC) {
+        console.warn("Not a frame message");
+        return null;
+    }
+
+    let version = view.getUint8(4);
+    if (version !== FRAME_FORMAT_VERSION) {
+        console.warn(`Unsupported frame format version: ${version}`);
+        return null;
+    }
+
+    return {
+        version,
+        kind: view.getUint8(5),
+        encoding: view.getUint8(6),
+        pixelFormat: view.getUint8(7),
+        sequence: Number(view.getBigUint64(8, true)),
+        timestamp: Number(view.getBigUint64(16, true)),
+        dpr: view.getFloat32(24, true),
+        width: view.getUint32(28, true),
+        height: view.getUint32(32, true),
+        rectCount: view.getUint32(36, true),
+    };
+}
+
+// Applies binary frame messages to a locally kept RGBA framebuffer.
+export class FrameDecoder {
+    private frame: Frame | null = null;
+
+    constructor(private requestKeyframe: () => void) { }
+
+    async apply(buffer: ArrayBuffer): Promise<DecodedFrame | null> {
+        let header = parseFrameHeader(buffer);
+        if (!header) {
+            return null;
+        }
+
+        let view = new DataView(buffer);
+        let { kind, encoding, width, height, rectCount, sequence, timestamp, dpr } = header;
+        let keyframe = kind === FrameKind.Key;
+
+        if (kind === FrameKind.Popup) {
+            let [rect, payload] = readRect(view, buffer, FRAME_HEADER_SIZE);
+            let data = await decodePixels(encoding, payload, width, height);
+            return { type: "Popup", data: { rect, data, sequence, timestamp, dpr } };
+        }
+
+        let sizeChanged = !this.frame || this.frame.width !== width || this.frame.height !== height;
+        if (!keyframe && sizeChanged) {
+            this.requestKeyframe();
+            return null;
+        }
+
+        if (keyframe && sizeChanged) {
+            this.frame = {
+                width, height, data: new Uint8Array(width * height * 4), keyframe, dirtyRects: [], sequence, timestamp, dpr
+            };
+        }
+
+        let frame = this.frame!;
+        let dirtyRects: Rect[] = [];
+        let offset = FRAME_HEADER_SIZE;
+        for (let i = 0; i < rectCount; i++) {
+            let [rect, payload] = readRect(view, buffer, offset);
+            let pixels = await decodePixels(encoding, payload, rect.width, rect.height);
+            offset += RECT_HEADER_SIZE + payload.length;
+
+            let rowLength = rect.width * 4;
+            for (let row = 0; row < rect.height; row++) {
+                let src = pixels.subarray(row * rowLength, (row + 1) * rowLength);
+                frame.data.set(src, ((rect.y + row) * width + rect.x) * 4);
+            }
+            dirtyRects.push(rect);
+        }
+
+        frame.keyframe = keyframe;
+        frame.dirtyRects = dirtyRects;
+        frame.sequence = sequence;
+        frame.timestamp = timestamp;
+        frame.dpr = dpr;
+        return { type: "Frame", data: frame };
+    }
+}
+
+function readRect(view: DataView, buffer: ArrayBuffer, offset: number): [Rect, Uint8Array] {
+    let rect: Rect = {
+        x: view.getUint32(offset, true),
+        y: view.getUint32(offset + 4, true),
+        width: view.getUint32(offset + 8, true),
+        height: view.getUint32(offset + 12, true),
+    };
+    let length = view.getUint32(offset + 16, true);
+    return [rect, new Uint8Array(buffer, offset + RECT_HEADER_SIZE, length)];
+}
+
+async function decodePixels(encoding: number, payload: Uint8Array, width: number, height: number): Promise<Uint8Array> {
+    switch (encoding) {
+        case EncodingId.Raw:
+            return payload;
+        case EncodingId.Qoi:
+            return decodeQoi(payload, width, height);
+        case EncodingId.Png:
+        case EncodingId.Jpeg:
+        case EncodingId.WebP: {
+            let bitmap = await createImageBitmap(new Blob([payload], { type: MIME_TYPES[encoding] }));
+            let canvas = new OffscreenCanvas(width, height);
+            let context = canvas.getContext("2d")!;
+            context.drawImage(bitmap, 0, 0);
+            bitmap.close();
+            return new Uint8Array(context.getImageData(0, 0, width, height).data.buffer);
+        }
+        default:
+            throw new Error(`Unsupported frame encoding: ${encoding}`);
+    }
+}
+
+function decodeQoi(data: Uint8Array, width: number, height: number): Uint8Array {
+    const QOI_HEADER_SIZE = 14;
+    let pixels = new Uint8Array(width * height * 4);
+    let index = new Uint8Array(64 * 4);
+    let [r, g, b, a] = [0, 0, 0, 255];
+    let run = 0;
+    let p = QOI_HEADER_SIZE;
+
+    for (let offset = 0; offset < pixels.length; offset += 4) {
+        if (run > 0) {
+            run--;
+        } else {
+            let tag = data[p++];
+            if (tag === 0xfe) {
+                [r, g, b] = [data[p], data[p + 1], data[p + 2]];
+                p += 3;
+            } else if (tag === 0xff) {
+                [r, g, b, a] = [data[p], data[p + 1], data[p + 2], data[p + 3]];
+                p += 4;
+            } else if ((tag & 0xc0) === 0x00) {
+                [r, g, b, a] = index.subarray(tag * 4, tag * 4 + 4);
+            } else if ((tag & 0xc0) === 0x40) {
+                r = (r + ((tag >> 4) & 0x03) - 2) & 0xff;
+                g = (g + ((tag >> 2) & 0x03) - 2) & 0xff;
+                b = (b + (tag & 0x03) - 2) & 0xff;
+            } else if ((tag & 0xc0) === 0x80) {
+                let next = data[p++];
+                let dg = (tag & 0x3f) - 32;
+                r = (r + dg - 8 + ((next >> 4) & 0x0f)) & 0xff;
+                g = (g + dg) & 0xff;
+                b = (b + dg - 8 + (next & 0x0f)) & 0xff;
+            } else {
+                run = tag & 0x3f;
+            }
+
+            let hash = ((r * 3 + g * 5 + b * 7 + a * 11) % 64) * 4;
+            index.set([r, g, b, a], hash);
+        }
+
+        pixels.set([r, g, b, a], offset);
+    }
+
+    return pixels;
+}
@@ -2,8 +2,9 @@ import { Browser } from "./browser.js";
 export { Config, setConfig, getConfig } from "./config.js";
 export { KeyCode } from "./keyboard.js";
 export { Browser } from "./browser.js";
-export { MouseButton, LoadState, LoadStatus, Cursor, DownloadProgress, FileDialog } from "./types.js";
+export { MouseButton, TouchPoint, TouchType, DropData, DroppedFile, ClipboardData, FindResult, FindOptions, Zoom, PermissionRequest, LoadState, LoadStatus, Cursor, CustomCursor, DownloadProgress, FileDialog, ImeComposition, TextRange, Frame, Popup, Rect, SharedFrame, FrameHeader, FrameTransport, FrameEncoding, AudioFormat, AudioStream, AudioPacket, EventStreamOptions, SubscriberStats, ScreenshotOptions, ScreenshotFormat, ScreenshotFit, PdfOptions, EmulationSettings, EmulatedViewport } from "./types.js";
 export { TabEventStream } from "./event_stream.js";
+export { parseFrameHeader } from "./frame.js";
 export { Tab } from "./tab.js";
 
 
@@ -1,7 +1,8 @@
 import { TabEventStream } from "./event_stream.js";
+import { frameQuery } from "./frame.js";
 import { KeyCode, keyCodeToMacOSVirtualKey, keyCodeToWindowsVirtualKey } from "./keyboard.js";
 import { MessageHandler } from "./messages.js";
-import { ClickableElement, detectPlatform, MouseButton, Platform, ScreenshotOptions } from "./types.js";
+import { ClickableElement, detectPlatform, EmulationSettings, EventStreamOptions, MouseButton, PdfOptions, Platform, ScreenshotOptions, SubscriberStats, TextRange, TouchPoint, DropData, ClipboardData, FindOptions, Zoom } from "./types.js";
 
 export class Tab {
     id: number;
@@ -26,16 +27,74 @@ export class Tab {
         return result.url;
     }
 
+    async zoom(): Promise<Zoom> {
+        return await this.messageHandler.send('getZoomLevel', { tab: this.id });
+    }
+
+    // The level is remembered for the origin of the page.
+    async setZoomLevel(level: number): Promise<void> {
+        await this.messageHandler.send('setZoomLevel', { tab: this.id, level });
+    }
+
+    async zoomIn(): Promise<void> {
+        await this.messageHandler.send('zoomIn', { tab: this.id });
+    }
+
+    async zoomOut(): Promise<void> {
+        await this.messageHandler.send('zoomOut', { tab: this.id });
+    }
+
+    async resetZoom(): Promise<void> {
+        await this.messageHandler.send('resetZoom', { tab: this.id });
+    }
+
+    async setAudioMuted(muted: boolean): Promise<void> {
+        await this.messageHandler.send('setAudioMuted', { tab: this.id, muted });
+    }
+
+    // Unless remember is false, the decision is kept for the origin of the request.
+    async respondPermission(id: number, allow: boolean, remember: boolean = true): Promise<void> {
+        await this.messageHandler.send('respondPermission', { tab: this.id, id, allow, remember });
+    }
+
+    async subscriberStats(): Promise<{ id: number, stats: SubscriberStats }[]> {
+        const result = await this.messageHandler.send('getSubscriberStats', { tab: this.id });
+        return result.subscribers;
+    }
+
     async screenshot(options?: ScreenshotOptions): Promise<string> {
-        const { width, height } = options?.size || { width: 800, height: 600 };
+        const fit = options?.fit || "exact";
+        const size = options?.size || (fit === "none" ? undefined : { width: 800, height: 600 });
         const result = await this.messageHandler.send('screenshot', {
             tab: this.id,
-            width,
-            height
+            width: size?.width,
+            height: size?.height,
+            clip: options?.clip,
+            full_page: options?.fullPage || false,
+            format: options?.format || "png",
+            quality: options?.quality,
+            fit,
+            fast: options?.fast || false
         });
         return result.screenshot;
     }
 
+    // Returns the base64 encoded PDF.
+    async printToPdf(options?: PdfOptions): Promise<string> {
+        const result = await this.messageHandler.send('printToPdf', pdfParams(this.id, options));
+        return result.pdf;
+    }
+
+    // Writes the PDF into the downloads directory of the server profile and returns its path.
+    async savePdf(fileName?: string, options?: PdfOptions): Promise<string> {
+        const result = await this.messageHandler.send('printToPdf', {
+            ...pdfParams(this.id, options),
+            save: true,
+            file_name: fileName
+        });
+        return result.path;
+    }
+
     async navigate(url: string, waitUntilLoaded: boolean = false): Promise<void> {
         await this.messageHandler.send('navigate', {
             tab: this.id,
@@ -68,13 +127,74 @@ export class Tab {
         });
     }
 
-    async click(x: number, y: number, button: MouseButton = MouseButton.Left, down: boolean = true): Promise<void> {
+    // Tells the page that the cursor has left the view at the given position.
+    async mouseLeave(x: number, y: number): Promise<void> {
+        await this.messageHandler.send('mouseMove', {
+            tab: this.id,
+            x: Math.floor(x),
+            y: Math.floor(y),
+            leave: true
+        });
+    }
+
+    // Without clickCount the server detects double and triple clicks itself.
+    async click(x: number, y: number, button: MouseButton = MouseButton.Left, down: boolean = true, clickCount?: number): Promise<void> {
         await this.messageHandler.send('click', {
             tab: this.id,
             x: Math.floor(x),
             y: Math.floor(y),
             button,
-            down
+            down,
+            click_count: clickCount
+        });
+    }
+
+    // Multi-touch gestures send one event per finger.
+    async touch(point: TouchPoint): Promise<void> {
+        await this.messageHandler.send('touch', {
+            tab: this.id,
+            id: point.id,
+            type: point.type,
+            x: point.x,
+            y: point.y,
+            radius_x: point.radiusX,
+            radius_y: point.radiusY,
+            rotation_angle: point.rotationAngle,
+            pressure: point.pressure
+        });
+    }
+
+    // Drags data from the client's machine over the page, files are uploaded
+    // with the first call. Drags started by the page itself follow mouse events.
+    async dragEnter(x: number, y: number, data: DropData): Promise<void> {
+        await this.messageHandler.send('dragEnter', {
+            tab: this.id,
+            x: Math.floor(x),
+            y: Math.floor(y),
+            files: data.files?.map((file) => ({ name: file.name, data: toBase64(file.data) })),
+            text: data.text,
+            html: data.html,
+            url: data.url
+        });
+    }
+
+    async dragOver(x: number, y: number): Promise<void> {
+        await this.messageHandler.send('dragOver', {
+            tab: this.id,
+            x: Math.floor(x),
+            y: Math.floor(y)
+        });
+    }
+
+    async dragLeave(): Promise<void> {
+        await this.messageHandler.send('dragLeave', { tab: this.id });
+    }
+
+    async drop(x: number, y: number): Promise<void> {
+        await this.messageHandler.send('drop', {
+            tab: this.id,
+            x: Math.floor(x),
+            y: Math.floor(y)
         });
     }
 
@@ -128,6 +248,53 @@ export class Tab {
         });
     }
 
+    // Types text key by key on the server, waiting delay milliseconds after every character.
+    async typeText(text: string, delay: number = 0): Promise<void> {
+        await this.messageHandler.send('typeText', {
+            tab: this.id,
+            text,
+            delay: Math.round(delay)
+        });
+    }
+
+    // Presses and releases a key combination, e.g. "Ctrl+Shift+T", "Enter" or "Shift+ArrowLeft".
+    async press(key: string): Promise<void> {
+        await this.messageHandler.send('press', {
+            tab: this.id,
+            key
+        });
+    }
+
+    // Starts or updates an IME composition. selection is the cursor position within
+    // text, replacement the range of existing text to be replaced.
+    async imeSetComposition(text: string, selection: TextRange, replacement?: TextRange): Promise<void> {
+        await this.messageHandler.send('imeSetComposition', {
+            tab: this.id,
+            text,
+            selection,
+            replacement
+        });
+    }
+
+    async imeCommitText(text: string, replacement?: TextRange): Promise<void> {
+        await this.messageHandler.send('imeCommitText', {
+            tab: this.id,
+            text,
+            replacement
+        });
+    }
+
+    async imeFinishComposingText(keepSelection: boolean = false): Promise<void> {
+        await this.messageHandler.send('imeFinishComposingText', {
+            tab: this.id,
+            keep_selection: keepSelection
+        });
+    }
+
+    async imeCancelComposition(): Promise<void> {
+        await this.messageHandler.send('imeCancelComposition', { tab: this.id });
+    }
+
     async clickableElements(): Promise<ClickableElement[]> {
         const result = await this.messageHandler.send('getClickableElements', { tab: this.id });
         return result.elements;
@@ -148,6 +315,50 @@ export class Tab {
         await this.messageHandler.send('startVideo', { tab: this.id });
     }
 
+    // Gives the tab its own size, browser-wide resizes no longer apply to it.
+    async resize(width: number, height: number): Promise<void> {
+        await this.messageHandler.send('resizeTab', {
+            tab: this.id,
+            width: Math.floor(width),
+            height: Math.floor(height)
+        });
+    }
+
+    // Replaces all emulation settings of the tab, they persist across navigations.
+    async setEmulation(settings: EmulationSettings): Promise<void> {
+        await this.messageHandler.send('setEmulation', {
+            tab: this.id,
+            viewport: settings.viewport && {
+                width: Math.floor(settings.viewport.width),
+                height: Math.floor(settings.viewport.height),
+                device_scale_factor: settings.viewport.deviceScaleFactor ?? 0,
+                mobile: settings.viewport.mobile ?? false
+            },
+            touch: settings.touch ?? false,
+            user_agent: settings.userAgent,
+            color_scheme: settings.colorScheme,
+            reduced_motion: settings.reducedMotion ?? false,
+            print_media: settings.printMedia ?? false
+        });
+    }
+
+    async clearEmulation(): Promise<void> {
+        await this.messageHandler.send('clearEmulation', { tab: this.id });
+    }
+
+    // Call when the viewer moves to a display with a different scale factor.
+    async setDevicePixelRatio(dpr: number): Promise<void> {
+        await this.messageHandler.send('setDevicePixelRatio', { tab: this.id, dpr });
+    }
+
+    async setFrameRate(frameRate: number, adaptive: boolean = false): Promise<void> {
+        await this.messageHandler.send('setFrameRate', {
+            tab: this.id,
+            frame_rate: Math.floor(frameRate),
+            adaptive
+        });
+    }
+
     async focus(focus: boolean): Promise<void> {
         await this.messageHandler.send('setFocus', {
             tab: this.id,
@@ -184,6 +395,31 @@ export class Tab {
         await this.messageHandler.send('cut', { tab: this.id });
     }
 
+    // Searches the page, results arrive as FindResult events.
+    async find(text: string, options?: FindOptions): Promise<void> {
+        await this.messageHandler.send('find', {
+            tab: this.id,
+            text,
+            forward: options?.forward ?? true,
+            match_case: options?.matchCase ?? false,
+            find_next: options?.findNext ?? false
+        });
+    }
+
+    async stopFinding(clearSelection: boolean = true): Promise<void> {
+        await this.messageHandler.send('stopFinding', { tab: this.id, clear_selection: clearSelection });
+    }
+
+    // Sets the content inserted by the next paste, e.g. the client's clipboard.
+    async setClipboard(clipboard: ClipboardData): Promise<void> {
+        await this.messageHandler.send('setClipboard', {
+            tab: this.id,
+            text: clipboard.text,
+            html: clipboard.html,
+            image: clipboard.image
+        });
+    }
+
     async delete(): Promise<void> {
         await this.messageHandler.send('delete', { tab: this.id });
     }
@@ -200,8 +436,32 @@ export class Tab {
         await this.messageHandler.send('cancelDownloading', { tab: this.id, download_id: downloadId });
     }
 
-    events(): TabEventStream {
-        let address = this.serverUrl.origin + "/tab/" + this.id;
+    events(options?: EventStreamOptions): TabEventStream {
+        let address = this.serverUrl.origin + "/tab/" + this.id + frameQuery(options?.encoding, options?.quality, options?.transport, options?.audio);
         return new TabEventStream(address);
     }
-}
\ No newline at end of file
+}
+
+function pdfParams(tab: number, options?: PdfOptions) {
+    return {
+        tab,
+        landscape: options?.landscape || false,
+        print_background: options?.printBackground || false,
+        paper_width: options?.paperWidth,
+        paper_height: options?.paperHeight,
+        margin_top: options?.marginTop,
+        margin_bottom: options?.marginBottom,
+        margin_left: options?.marginLeft,
+        margin_right: options?.marginRight,
+        page_ranges: options?.pageRanges || ""
+    };
+}
+
+function toBase64(data: Uint8Array): string {
+    const CHUNK_SIZE = 0x8000;
+    let binary = "";
+    for (let i = 0; i < data.length; i += CHUNK_SIZE) {
+        binary += String.fromCharCode(...data.subarray(i, i + CHUNK_SIZE));
+    }
+    return btoa(binary);
+}
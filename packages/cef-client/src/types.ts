@@ -1,13 +1,62 @@
 export interface OpenTabOptions {
     url: string;
     wait_until_loaded: boolean;
+    frame_rate: number;
+    adaptive_frame_rate: boolean;
+    // Size of the tab, the browser-wide size is used when not set.
+    width: number;
+    height: number;
 }
 
+export type ScreenshotFormat = "png" | "jpeg" | "webp";
+
+// exact: stretch to the size, contain: keep the aspect ratio, none: keep the captured size.
+export type ScreenshotFit = "exact" | "contain" | "none";
+
 export interface ScreenshotOptions {
     size?: {
         width: number;
         height: number;
     }
+    // Region of the page in CSS pixels, the viewport by default.
+    clip?: Rect;
+    fullPage?: boolean;
+    format?: ScreenshotFormat;
+    quality?: number;
+    fit?: ScreenshotFit;
+    // Encode the tab's framebuffer directly, clip is then relative to the viewport.
+    fast?: boolean;
+}
+
+// Device metrics in CSS pixels, deviceScaleFactor 0 keeps the tab's own DPR.
+export interface EmulatedViewport {
+    width: number;
+    height: number;
+    deviceScaleFactor?: number;
+    mobile?: boolean;
+}
+
+export interface EmulationSettings {
+    viewport?: EmulatedViewport;
+    touch?: boolean;
+    userAgent?: string;
+    colorScheme?: "light" | "dark";
+    reducedMotion?: boolean;
+    printMedia?: boolean;
+}
+
+// Sizes are in inches.
+export interface PdfOptions {
+    landscape?: boolean;
+    printBackground?: boolean;
+    paperWidth?: number;
+    paperHeight?: number;
+    marginTop?: number;
+    marginBottom?: number;
+    marginLeft?: number;
+    marginRight?: number;
+    // e.g. "1-5, 8"
+    pageRanges?: string;
 }
 
 export interface ClickableElement {
@@ -40,6 +89,71 @@ export enum MouseButton {
     Right = 2,
 }
 
+// activeMatch is the 1-based index of the highlighted match. Results with
+// finalUpdate unset may have a partial count.
+export interface FindResult {
+    identifier: number;
+    count: number;
+    activeMatch: number;
+    selectionRect: Rect;
+    finalUpdate: boolean;
+}
+
+export interface FindOptions {
+    forward?: boolean;
+    matchCase?: boolean;
+    // Continues the previous search instead of starting a new one.
+    findNext?: boolean;
+}
+
+// Permissions are named e.g. "camera", "microphone", "geolocation", "notifications" or "clipboard".
+export interface PermissionRequest {
+    id: number;
+    origin: string;
+    permissions: string[];
+}
+
+// Zoom levels are logarithmic, factor is 1.2^level and level 0 is 100%.
+export interface Zoom {
+    level: number;
+    factor: number;
+}
+
+// Clipboard content of a tab. image is a base64 encoded PNG.
+export interface ClipboardData {
+    text?: string;
+    html?: string;
+    image?: string;
+}
+
+export interface DroppedFile {
+    name: string;
+    data: Uint8Array;
+}
+
+// Data dragged onto the page from the client's machine.
+export interface DropData {
+    files?: DroppedFile[];
+    text?: string;
+    html?: string;
+    url?: string;
+}
+
+export type TouchType = "pressed" | "moved" | "released" | "cancelled";
+
+// A single touch point, id identifies the finger across its events. Coordinates
+// and radii are in CSS pixels, pressure is between 0 and 1.
+export interface TouchPoint {
+    id: number;
+    type: TouchType;
+    x: number;
+    y: number;
+    radiusX?: number;
+    radiusY?: number;
+    rotationAngle?: number;
+    pressure?: number;
+}
+
 export enum LoadStatus {
     Loading = 0,
     Loaded = 1,
@@ -54,6 +168,16 @@ export type LoadState = {
     errorMessage?: string;
 };
 
+// A cursor image set by the page. image is a base64 encoded PNG and scale the
+// number of image pixels per CSS pixel.
+export interface CustomCursor {
+    hash: string;
+    image: string;
+    hotspotX: number;
+    hotspotY: number;
+    scale: number;
+}
+
 export enum Cursor {
     Pointer = "Pointer",
     Hand = "Hand",
@@ -79,8 +203,117 @@ export interface FileDialog {
     accept_descriptions: string[];
 }
 
+// A range of characters, to is exclusive.
+export interface TextRange {
+    from: number;
+    to: number;
+}
+
+// characterBounds are in CSS pixels relative to the view, use them to place
+// the IME candidate window.
+export interface ImeComposition {
+    range: TextRange;
+    characterBounds: Rect[];
+}
+
+export interface SubscriberStats {
+    queuedEvents: number;
+    queuedFrames: number;
+    sentFrames: number;
+    droppedFrames: number;
+    droppedAudioPackets: number;
+}
+
+export type FrameEncoding = "raw" | "png" | "jpeg" | "webp" | "qoi";
+
+export interface EventStreamOptions {
+    encoding?: FrameEncoding;
+    // JPEG quality, 1-100.
+    quality?: number;
+    // "shm" writes frames into a shared-memory file and emits FrameReady instead of Frame.
+    // Only for clients on the same host as the server.
+    transport?: FrameTransport;
+    // Streams the tab's audio as AudioPacket events, not sent unless set.
+    audio?: AudioFormat;
+}
+
+export type FrameTransport = "websocket" | "shm";
+
+// "s16" halves the bandwidth, packets are always decoded to floats.
+// "opus" needs a server built with the opus feature, see AudioPacket.opus.
+export type AudioFormat = "f32" | "s16" | "opus";
+
+export interface AudioStream {
+    sampleRate: number;
+    channels: number;
+    channelLayout: string;
+    framesPerBuffer: number;
+}
+
+// data holds frames * channels interleaved samples between -1 and 1,
+// pts is the presentation timestamp in milliseconds.
+// Opus streams carry 20 ms Opus packets in opus instead, data is empty. They can be
+// decoded with a WebCodecs AudioDecoder. Streams Opus can't encode are sent as samples.
+export interface AudioPacket {
+    data: Float32Array;
+    opus?: Uint8Array[];
+    frames: number;
+    channels: number;
+    sampleRate: number;
+    pts: number;
+}
+
+// A frame in the shared-memory ring: width * height * 4 RGBA bytes at offset in the file at path.
+// The u64 sequence at offset - 16 must still match after reading the pixels.
+export interface SharedFrame {
+    path: string;
+    sequence: number;
+    slot: number;
+    offset: number;
+    width: number;
+    height: number;
+    dpr: number;
+    dirtyRects: Rect[];
+}
+
+// A popup layer, data holds rect.width * rect.height RGBA pixels.
+export interface Popup {
+    rect: Rect;
+    data: Uint8Array;
+    sequence: number;
+    timestamp: number;
+    dpr: number;
+}
+
+export interface Rect {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
 export interface Frame {
     width: number;
     height: number;
     data: Uint8Array;
+    keyframe: boolean;
+    dirtyRects: Rect[];
+    // Grows by one with every paint, a gap means frames were merged on the server.
+    sequence: number;
+    // Paint time in microseconds since the UNIX epoch.
+    timestamp: number;
+    dpr: number;
+}
+
+export interface FrameHeader {
+    version: number;
+    kind: number;
+    encoding: number;
+    pixelFormat: number;
+    sequence: number;
+    timestamp: number;
+    dpr: number;
+    width: number;
+    height: number;
+    rectCount: number;
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 import { ChildProcess, spawn } from "child_process";
-import { dirname, resolve } from "path";
+import { readFile } from "fs/promises";
+import { createServer, Server } from "http";
+import { basename, dirname, resolve } from "path";
 import { fileURLToPath, pathToFileURL } from "url";
 
 export const pollTimeout = { timeout: 5000, interval: 200 };
@@ -20,6 +22,48 @@ export const getPageUrl = (page: string) => {
     return pathToFileURL(fullPath).href;
 };
 
+// See FRAME_FORMAT_VERSION in crates/huly-cef/src/messages.rs for the layout.
+const FRAME_HEADER_SIZE = 40;
+const RECT_HEADER_SIZE = 20;
+
+// Opens a tab socket without the client's decoder and returns the encoded pixels
+// of the first rect of the first frame.
+export async function firstFramePayload(url: string): Promise<Buffer> {
+    const websocket = new WebSocket(url);
+    websocket.binaryType = "arraybuffer";
+    try {
+        const buffer = await new Promise<ArrayBuffer>((resolve, reject) => {
+            websocket.onmessage = (event) => {
+                if (event.data instanceof ArrayBuffer) {
+                    resolve(event.data);
+                }
+            };
+            websocket.onerror = () => reject(`Failed to connect to ${url}`);
+        });
+        const length = new DataView(buffer).getUint32(FRAME_HEADER_SIZE + RECT_HEADER_SIZE - 4, true);
+        return Buffer.from(buffer, FRAME_HEADER_SIZE + RECT_HEADER_SIZE, length);
+    } finally {
+        websocket.close();
+    }
+}
+
+// Serves the test pages over http, pages on different ports have different origins.
+export async function serveTestPages(port: number): Promise<Server> {
+    const server = createServer(async (req, res) => {
+        const page = basename(new URL(req.url ?? "/", "http://localhost").pathname) || "title.html";
+        try {
+            const content = await readFile(resolve(testdir, "testpages", page));
+            res.writeHead(200, { "Content-Type": page.endsWith(".svg") ? "image/svg+xml" : "text/html" });
+            res.end(content);
+        } catch {
+            res.writeHead(404);
+            res.end();
+        }
+    });
+    await new Promise<void>(resolve => server.listen(port, resolve));
+    return server;
+}
+
 export interface CefProcess {
     cef: ChildProcess;
     finished: Promise<number | null>;
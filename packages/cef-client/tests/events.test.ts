@@ -1,8 +1,10 @@
 import { afterAll, beforeAll, describe, expect, test } from 'vitest';
+import sharp from 'sharp';
+import { existsSync, readFileSync } from 'fs';
 
-import { Browser, connect } from '../src/index';
-import { Cursor, LoadState, LoadStatus } from '../src/types';
-import { CefProcess, getPageUrl, launchCef, pollTimeout } from './common';
+import { Browser, connect, MouseButton, Tab, TabEventStream } from '../src/index';
+import { AudioPacket, AudioStream, ClipboardData, Cursor, CustomCursor, FindResult, Frame, LoadState, LoadStatus, PermissionRequest, Popup, Rect, SharedFrame } from '../src/types';
+import { CefProcess, firstFramePayload, getPageUrl, launchCef, pollTimeout, serveTestPages } from './common';
 
 describe('tab events', () => {
     let cef_process: CefProcess;
@@ -109,6 +111,389 @@ describe('tab events', () => {
         expect(fs.readFileSync(filePath, 'utf8')).toBe(content);
     });
 
+    test('delta frames', async () => {
+        browser.resize(800, 600);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+
+        let frames: { keyframe: boolean, dirtyRects: Rect[] }[] = [];
+        let stream = tab.events();
+        stream.on("Frame", (frame) => frames.push({ keyframe: frame.keyframe, dirtyRects: frame.dirtyRects }));
+
+        tab.startVideo();
+        await expect.poll(() => frames.length, pollTimeout).toBeGreaterThan(10);
+
+        // A new stream starts with a keyframe, after that only the animated circle is sent.
+        expect(frames[0]).toStrictEqual({ keyframe: true, dirtyRects: [{ x: 0, y: 0, width: 800, height: 600 }] });
+        let deltas = frames.filter((frame) => !frame.keyframe);
+        expect(deltas.length).toBeGreaterThan(0);
+        for (let rect of deltas.flatMap((frame) => frame.dirtyRects)) {
+            expect(rect.width * rect.height).toBeLessThan(800 * 600);
+        }
+
+        frames = [];
+        stream.requestKeyframe();
+        await expect.poll(() => frames.some((frame) => frame.keyframe), pollTimeout).toBe(true);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('frame encodings', async () => {
+        browser.resize(320, 240);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        tab.startVideo();
+
+        // Image decoding in the client needs a DOM, so these are checked with sharp.
+        for (let encoding of ["png", "jpeg", "webp"] as const) {
+            const payload = await firstFramePayload(`ws://localhost:${port}/tab/${tab.id}?encoding=${encoding}`);
+            const metadata = await sharp(payload).metadata();
+            expect(metadata.format).toBe(encoding);
+            expect([metadata.width, metadata.height]).toEqual([320, 240]);
+        }
+
+        let frame: Frame | null = null;
+        let stream = tab.events({ encoding: "qoi" });
+        stream.on("Frame", (data) => frame = data);
+        await expect.poll(() => frame?.width, pollTimeout).toBe(320);
+        expect(frame!.height).toBe(240);
+        // The background of events.html is #222.
+        expect(Array.from(frame!.data.subarray(0, 4))).toEqual([0x22, 0x22, 0x22, 0xff]);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('subscriber stats', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        let frames = 0;
+        let stream = tab.events();
+        stream.on("Frame", () => frames++);
+
+        tab.startVideo();
+        await expect.poll(() => frames, pollTimeout).toBeGreaterThan(10);
+
+        const subscribers = await tab.subscriberStats();
+        expect(subscribers.length).toBe(1);
+        const stats = subscribers[0].stats;
+        expect(stats.sentFrames).toBeGreaterThanOrEqual(frames);
+        expect(stats.queuedFrames).toBeLessThanOrEqual(1);
+
+        stream.closeConnection();
+        await expect.poll(async () => (await tab.subscriberStats()).length, pollTimeout).toBe(0);
+        tab.close();
+    });
+
+    test('frame rate', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true, frame_rate: 10 });
+        let frames = 0;
+        let stream = tab.events();
+        stream.on("Frame", () => frames++);
+
+        const framesPerSecond = async () => {
+            const start = frames;
+            await new Promise(resolve => setTimeout(resolve, 1000));
+            return frames - start;
+        };
+
+        // The circle of events.html is animated, so the tab paints at its frame rate.
+        tab.startVideo();
+        await expect.poll(() => frames, pollTimeout).toBeGreaterThan(0);
+        expect(await framesPerSecond()).toBeLessThanOrEqual(12);
+
+        await tab.setFrameRate(60);
+        expect(await framesPerSecond()).toBeGreaterThan(20);
+
+        await tab.setFrameRate(5);
+        await new Promise(resolve => setTimeout(resolve, 200));
+        expect(await framesPerSecond()).toBeLessThanOrEqual(7);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('popup', async () => {
+        browser.resize(800, 600);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        let popup: Popup | null = null;
+        let hidden = false;
+        let stream = tab.events();
+        stream.on("Popup", (data) => popup = data);
+        stream.on("PopupHidden", () => hidden = true);
+        tab.startVideo();
+
+        // Opens the <select> in the top left corner of events.html.
+        await tab.click(45, 42, MouseButton.Left, true);
+        await tab.click(45, 42, MouseButton.Left, false);
+        await expect.poll(() => popup?.rect.width ?? 0, pollTimeout).toBeGreaterThan(0);
+        expect(popup!.data.length).toBe(popup!.rect.width * popup!.rect.height * 4);
+        expect(hidden).toBe(false);
+
+        await tab.click(400, 500, MouseButton.Left, true);
+        await tab.click(400, 500, MouseButton.Left, false);
+        await expect.poll(() => hidden, pollTimeout).toBe(true);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('device pixel ratio', async () => {
+        browser.resize(400, 300);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        let size = [0, 0];
+        let stream = tab.events();
+        stream.on("Frame", (frame) => size = [frame.width, frame.height]);
+        tab.startVideo();
+        await expect.poll(() => size, pollTimeout).toEqual([400, 300]);
+
+        // Frames are in physical pixels.
+        await tab.setDevicePixelRatio(2);
+        await expect.poll(() => size, pollTimeout).toEqual([800, 600]);
+
+        await expect(tab.setDevicePixelRatio(0)).rejects.toThrow("invalid device pixel ratio");
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('shared-memory frames', async () => {
+        browser.resize(320, 240);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        let frame: SharedFrame | null = null;
+        let frames = 0;
+        let stream = tab.events({ transport: "shm" });
+        stream.on("FrameReady", (data) => frame = data);
+        stream.on("Frame", () => frames++);
+        tab.startVideo();
+
+        await expect.poll(() => frame?.width, pollTimeout).toBe(320);
+        const { path, offset, width, height } = frame!;
+        const file = readFileSync(path);
+        expect(file.subarray(0, 4).toString()).toBe("HCEF");
+        expect(file.length).toBeGreaterThanOrEqual(offset + width * height * 4);
+        // The background of events.html is #222.
+        expect(Array.from(file.subarray(offset, offset + 4))).toEqual([0x22, 0x22, 0x22, 0xff]);
+        expect(frames).toBe(0);
+
+        // The file is removed with the last shared-memory stream of the tab.
+        stream.closeConnection();
+        await expect.poll(() => existsSync(path), pollTimeout).toBe(false);
+        tab.close();
+    });
+
+    test('frame metadata', async () => {
+        browser.resize(800, 600);
+        const tab = await browser.openTab({ url: getPageUrl("events.html"), wait_until_loaded: true });
+        let frames: { sequence: number, timestamp: number, dpr: number }[] = [];
+        let stream = tab.events();
+        stream.on("Frame", (frame) => frames.push({ sequence: frame.sequence, timestamp: frame.timestamp, dpr: frame.dpr }));
+
+        tab.startVideo();
+        await expect.poll(() => frames.length, pollTimeout).toBeGreaterThan(10);
+
+        for (let i = 1; i < frames.length; i++) {
+            expect(frames[i].sequence).toBeGreaterThan(frames[i - 1].sequence);
+            expect(frames[i].timestamp).toBeGreaterThanOrEqual(frames[i - 1].timestamp);
+        }
+        // Timestamps are in microseconds since the UNIX epoch.
+        expect(Math.abs(frames[0].timestamp / 1000 - Date.now())).toBeLessThan(60_000);
+        expect(frames.every((frame) => frame.dpr === 1)).toBe(true);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('custom cursor', async () => {
+        browser.resize(800, 600);
+        const tab = await browser.openTab({ url: getPageUrl("cursor.html"), wait_until_loaded: true });
+        let cursors: CustomCursor[] = [];
+        let pointer = false;
+        let stream = tab.events();
+        stream.on("CustomCursor", (cursor) => cursors.push(cursor));
+        stream.on("Cursor", (cursor) => pointer ||= cursor === Cursor.Hand);
+        tab.startVideo();
+
+        // The left half of cursor.html uses a 16x16 canvas cursor, the right half a pointer.
+        await tab.mouseMove(100, 100);
+        await expect.poll(() => cursors.length, pollTimeout).toBe(1);
+        expect(cursors[0].hash).toMatch(/^[0-9a-f]{16}$/);
+        expect([cursors[0].hotspotX, cursors[0].hotspotY, cursors[0].scale]).toEqual([4, 6, 1]);
+        const metadata = await sharp(Buffer.from(cursors[0].image, 'base64')).metadata();
+        expect([metadata.width, metadata.height]).toEqual([16, 16]);
+
+        await tab.mouseMove(700, 100);
+        await expect.poll(() => pointer, pollTimeout).toBe(true);
+
+        // The image is only sent once, the stream fills it in from its cache.
+        await tab.mouseMove(100, 100);
+        await expect.poll(() => cursors.length, pollTimeout).toBe(2);
+        expect(cursors[1]).toEqual(cursors[0]);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('clipboard', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("keyboard.html"), wait_until_loaded: true });
+        let clipboard: ClipboardData | null = null;
+        let stream = tab.events();
+        stream.on("Clipboard", (data) => clipboard = data);
+
+        await tab.setClipboard({ text: "from the client" });
+        await tab.paste();
+        await tab.press("Enter");
+        await expect.poll(() => tab.title(), pollTimeout).toBe("from the client");
+
+        await tab.typeText(" and the page");
+        await tab.selectAll();
+        await tab.copy();
+        await expect.poll(() => clipboard?.text, pollTimeout).toBe("from the client and the page");
+
+        // The page copy is pasted next.
+        await tab.paste();
+        await tab.press("Enter");
+        await expect.poll(() => tab.title(), pollTimeout).toBe("from the client and the page");
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('find in page', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("find.html"), wait_until_loaded: true });
+        let result: FindResult | null = null;
+        let stream = tab.events();
+        stream.on("FindResult", (data) => {
+            if (data.finalUpdate) {
+                result = data;
+            }
+        });
+
+        await tab.find("apple");
+        await expect.poll(() => result?.count, pollTimeout).toBe(4);
+        expect(result!.activeMatch).toBe(1);
+
+        await tab.find("apple", { findNext: true });
+        await expect.poll(() => result?.activeMatch, pollTimeout).toBe(2);
+
+        result = null;
+        await tab.find("apple", { matchCase: true });
+        await expect.poll(() => result?.count, pollTimeout).toBe(3);
+
+        await tab.stopFinding();
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('audio', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("audio.html"), wait_until_loaded: true });
+        let events: string[] = [];
+        let audio: AudioStream | null = null;
+        let packets: AudioPacket[] = [];
+        let stream = tab.events({ audio: "s16" });
+        stream.on("AudioStarted", (data) => {
+            events.push("started");
+            audio = data;
+        });
+        stream.on("AudioPacket", (packet) => {
+            if (events[events.length - 1] !== "packet") {
+                events.push("packet");
+            }
+            packets.push(packet);
+        });
+        stream.on("AudioStopped", () => events.push("stopped"));
+
+        await tab.click(100, 100, MouseButton.Left, true);
+        await tab.click(100, 100, MouseButton.Left, false);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Playing");
+        await expect.poll(() => packets.length, pollTimeout).toBeGreaterThan(10);
+
+        expect(audio!.sampleRate).toBeGreaterThan(0);
+        for (let i = 0; i < packets.length; i++) {
+            expect(packets[i].channels).toBe(audio!.channels);
+            expect(packets[i].sampleRate).toBe(audio!.sampleRate);
+            expect(packets[i].data.length).toBe(packets[i].frames * packets[i].channels);
+            if (i > 0) {
+                expect(packets[i].pts).toBeGreaterThan(packets[i - 1].pts);
+            }
+        }
+        expect(packets.some((packet) => packet.data.some((sample) => Math.abs(sample) > 0.1))).toBe(true);
+
+        await tab.click(100, 100, MouseButton.Left, true);
+        await tab.click(100, 100, MouseButton.Left, false);
+        await expect.poll(() => events[events.length - 1], pollTimeout).toBe("stopped");
+        expect(events).toEqual(["started", "packet", "stopped"]);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('audio muting', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("audio.html"), wait_until_loaded: true });
+        let audible: boolean | null = null;
+        let muted: boolean | null = null;
+        let stream = tab.events();
+        stream.on("Audible", (value) => audible = value);
+        stream.on("AudioMuted", (value) => muted = value);
+
+        await tab.click(100, 100, MouseButton.Left, true);
+        await tab.click(100, 100, MouseButton.Left, false);
+        await expect.poll(() => audible, pollTimeout).toBe(true);
+
+        await tab.setAudioMuted(true);
+        await expect.poll(() => muted, pollTimeout).toBe(true);
+        await tab.setAudioMuted(false);
+        await expect.poll(() => muted, pollTimeout).toBe(false);
+
+        await tab.click(100, 100, MouseButton.Left, true);
+        await tab.click(100, 100, MouseButton.Left, false);
+        await expect.poll(() => audible, pollTimeout).toBe(false);
+
+        stream.closeConnection();
+        tab.close();
+    });
+
+    test('permissions', async () => {
+        const [allowed, denied] = await Promise.all([serveTestPages(8093), serveTestPages(8094)]);
+        let tabs: Tab[] = [];
+        let streams: TabEventStream[] = [];
+        try {
+            let requests: PermissionRequest[] = [];
+            const open = async (url: string) => {
+                const tab = await browser.openTab({ url });
+                const stream = tab.events();
+                stream.on("PermissionRequest", (request) => requests.push(request));
+                tabs.push(tab);
+                streams.push(stream);
+                return tab;
+            };
+
+            let tab = await open("http://localhost:8093/permissions.html");
+            await expect.poll(() => requests.length, pollTimeout).toBe(1);
+            expect(requests[0].origin).toContain("localhost:8093");
+            expect(requests[0].permissions).toContain("notifications");
+            await tab.respondPermission(requests[0].id, true);
+            await expect.poll(() => tab.title(), pollTimeout).toBe("granted");
+
+            tab = await open("http://localhost:8094/permissions.html");
+            await expect.poll(() => requests.length, pollTimeout).toBe(2);
+            await tab.respondPermission(requests[1].id, false);
+            await expect.poll(() => tab.title(), pollTimeout).toBe("denied");
+
+            // Both decisions are remembered for their origins.
+            const again = await Promise.all([
+                open("http://localhost:8093/permissions.html"),
+                open("http://localhost:8094/permissions.html"),
+            ]);
+            await expect.poll(() => again[0].title(), pollTimeout).toBe("granted");
+            await expect.poll(() => again[1].title(), pollTimeout).toBe("denied");
+            expect(requests.length).toBe(2);
+        } finally {
+            streams.forEach((stream) => stream.closeConnection());
+            tabs.forEach((tab) => tab.close());
+            allowed.close();
+            denied.close();
+        }
+    });
+
     test('new tab', async () => {
     });
 
@@ -1,9 +1,11 @@
 import { afterAll, afterEach, beforeAll, describe, expect, test } from 'vitest';
 import sharp from 'sharp';
+import { readFileSync, rmSync } from 'fs';
+import { basename, dirname } from 'path';
 
-import { Browser, connect, KeyCode, MouseButton, Tab } from '../src/index';
+import { Browser, connect, ImeComposition, KeyCode, MouseButton, Tab } from '../src/index';
 
-import { pollTimeout, getPageUrl, launchCef, CefProcess } from './common';
+import { pollTimeout, getPageUrl, launchCef, serveTestPages, CefProcess } from './common';
 
 describe('tabs', () => {
     let browser: Browser;
@@ -48,6 +50,27 @@ describe('tabs', () => {
         await expect.poll(() => tab.title(), pollTimeout).toBe("1024x768");
     });
 
+    test('resize a single tab', async () => {
+        browser.resize(800, 600);
+
+        const url = getPageUrl("resize.html");
+        const shared = await browser.openTab({ url });
+        const custom = await browser.openTab({ url, width: 640, height: 480 });
+        await expect.poll(() => custom.title(), pollTimeout).toBe("640x480");
+
+        await custom.resize(320, 240);
+        await expect.poll(() => custom.title(), pollTimeout).toBe("320x240");
+
+        browser.resize(1024, 768);
+        await expect.poll(() => shared.title(), pollTimeout).toBe("1024x768");
+        expect(await custom.title()).toBe("320x240");
+
+        await expect(browser.openTab({ url, width: 640 })).rejects.toThrow("width and height");
+        await expect(browser.openTab({ url, width: 0, height: 480 })).rejects.toThrow("invalid size");
+        await expect(custom.resize(320, 0)).rejects.toThrow("invalid size");
+        expect(await custom.title()).toBe("320x240");
+    });
+
     test('go to a url', async () => {
         const tab = await browser.openTab({ url: "", wait_until_loaded: true });
         expect(await tab.title()).toBe("New Tab");
@@ -115,6 +138,33 @@ describe('tabs', () => {
         await expect.poll(() => tab.title(), pollTimeout).toMatch("Scroll: (250, 350) Delta: (-30, -50)");
     });
 
+    test('click count', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("click.html"), wait_until_loaded: true });
+
+        await tab.click(100, 100, MouseButton.Left, true);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Down: 1 Buttons: 1");
+        await tab.click(100, 100, MouseButton.Left, false);
+        await tab.click(101, 101, MouseButton.Left, true);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Down: 2 Buttons: 1");
+        await tab.click(101, 101, MouseButton.Left, false);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Double Click");
+
+        await tab.click(300, 300, MouseButton.Right, true, 3);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Down: 3 Buttons: 2");
+        await tab.click(300, 300, MouseButton.Right, false);
+
+        // Buttons that are still held down are reported with later presses.
+        await tab.click(200, 200, MouseButton.Middle, true);
+        await tab.click(200, 200, MouseButton.Left, true);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Down: 1 Buttons: 5");
+        await tab.click(200, 200, MouseButton.Left, false);
+        await tab.click(200, 200, MouseButton.Middle, false);
+
+        await tab.mouseMove(200, 200);
+        await tab.mouseLeave(200, -1);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Leave");
+    });
+
     test.skip('keyboard', async () => {
         let press = async (tab: Tab, code: KeyCode) => {
             tab.key(code, 0, true, false, false);
@@ -152,6 +202,94 @@ describe('tabs', () => {
         await expect.poll(() => tab.title(), pollTimeout).toBe(text.slice(0, -2));
     });
 
+    test('type text', async () => {
+        let tab = await browser.openTab({ url: getPageUrl("keyboard.html"), wait_until_loaded: true });
+        expect(await tab.title()).toBe("Keyboard");
+
+        const text = "Hello, World! Café こんにちは 🚀";
+        await tab.typeText(text);
+        await tab.press("Enter");
+        await expect.poll(() => tab.title(), pollTimeout).toBe(text);
+
+        await tab.press("Shift+ArrowLeft");
+        await tab.press("Backspace");
+        await tab.press("Enter");
+        await expect.poll(() => tab.title(), pollTimeout).toBe(text.slice(0, -2));
+    });
+
+    test('ime', async () => {
+        let tab = await browser.openTab({ url: getPageUrl("ime.html"), wait_until_loaded: true });
+        let compositions: ImeComposition[] = [];
+        let stream = tab.events();
+        stream.on("ImeComposition", (composition) => compositions.push(composition));
+        tab.startVideo();
+
+        await tab.imeSetComposition("にほん", { from: 3, to: 3 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Composing: にほん");
+        await expect.poll(() => compositions.length, pollTimeout).toBeGreaterThan(0);
+        const composition = compositions[compositions.length - 1];
+        expect(composition.range).toEqual({ from: 0, to: 3 });
+        expect(composition.characterBounds.length).toBe(3);
+
+        await tab.imeCommitText("日本");
+        await expect.poll(() => tab.title(), pollTimeout).toBe("日本");
+
+        await tab.imeSetComposition("ご", { from: 1, to: 1 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Composing: ご");
+        await tab.imeCancelComposition();
+        await tab.imeCommitText("語");
+        await expect.poll(() => tab.title(), pollTimeout).toBe("日本語");
+
+        stream.closeConnection();
+    });
+
+    test('touch', async () => {
+        let tab = await browser.openTab({ url: getPageUrl("touch.html"), wait_until_loaded: true });
+
+        await tab.touch({ id: 0, type: "pressed", x: 100, y: 200 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Start: 1 (100, 200)");
+
+        await tab.touch({ id: 1, type: "pressed", x: 300, y: 400 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Start: 2 (300, 400)");
+
+        await tab.touch({ id: 0, type: "moved", x: 150, y: 250, pressure: 0.5 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Move: 2 (150, 250)");
+
+        await tab.touch({ id: 1, type: "released", x: 300, y: 400 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("End: 1 (300, 400)");
+
+        await tab.touch({ id: 0, type: "released", x: 150, y: 250 });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("End: 0 (150, 250)");
+    });
+
+    test('drag and drop', async () => {
+        let tab = await browser.openTab({ url: getPageUrl("drop.html"), wait_until_loaded: true });
+
+        const data = new TextEncoder().encode("hello");
+        await tab.dragEnter(10, 10, { text: "dropped", files: [{ name: "../hello.txt", data }] });
+        await tab.dragOver(300, 300);
+        await tab.drop(300, 300);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Drop: dropped [hello.txt=hello]");
+
+        // Outside of the target the drop is ignored.
+        await tab.dragEnter(10, 10, { text: "ignored" });
+        await tab.dragOver(500, 500);
+        await tab.drop(500, 500);
+        await tab.dragEnter(10, 10, { text: "left" });
+        await tab.dragOver(300, 300);
+        await tab.dragLeave();
+        await new Promise(resolve => setTimeout(resolve, 200));
+        expect(await tab.title()).toBe("Drop: dropped [hello.txt=hello]");
+
+        // A drag started by the page follows the mouse.
+        await tab.click(50, 50, MouseButton.Left, true);
+        await tab.mouseMove(60, 60);
+        await tab.mouseMove(200, 200);
+        await tab.mouseMove(300, 300);
+        await tab.click(300, 300, MouseButton.Left, false);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("Drop: internal []");
+    });
+
     test('screenshot', async () => {
         browser.resize(1920, 1080);
 
@@ -169,6 +307,105 @@ describe('tabs', () => {
         expect(metadata.format).toBe('png');
     });
 
+    test('screenshot with contain fit', async () => {
+        browser.resize(1920, 1080);
+
+        const tab = await browser.openTab({ url: getPageUrl("title.html"), wait_until_loaded: true });
+        const screenshot = await tab.screenshot({ size: { width: 800, height: 600 }, fit: "contain", format: "jpeg", quality: 70 });
+
+        const metadata = await sharp(Buffer.from(screenshot, 'base64')).metadata();
+        expect(metadata.width).toBe(800);
+        expect(metadata.height).toBe(450);
+        expect(metadata.format).toBe('jpeg');
+    });
+
+    test('fast screenshot', async () => {
+        browser.resize(800, 600);
+
+        // The tab isn't streaming video, it is painted just for the screenshot.
+        const tab = await browser.openTab({ url: getPageUrl("title.html"), wait_until_loaded: true });
+        const screenshot = await tab.screenshot({ fast: true, fit: "none" });
+
+        let metadata = await sharp(Buffer.from(screenshot, 'base64')).metadata();
+        expect([metadata.width, metadata.height]).toEqual([800, 600]);
+        expect(metadata.format).toBe('png');
+
+        const clip = { x: 10, y: 20, width: 100, height: 50 };
+        const clipped = await tab.screenshot({ fast: true, fit: "none", clip, format: "jpeg" });
+        metadata = await sharp(Buffer.from(clipped, 'base64')).metadata();
+        expect([metadata.width, metadata.height]).toEqual([100, 50]);
+        expect(metadata.format).toBe('jpeg');
+
+        await expect(tab.screenshot({ fast: true, fullPage: true })).rejects.toThrow("full page");
+    });
+
+    test('emulation', async () => {
+        browser.resize(800, 600);
+
+        const tab = await browser.openTab({ url: getPageUrl("emulation.html"), wait_until_loaded: true });
+        await expect.poll(() => tab.title(), pollTimeout).toMatch(/^800x600 light /);
+
+        await tab.setEmulation({
+            viewport: { width: 390, height: 844, deviceScaleFactor: 3 },
+            colorScheme: "dark",
+            userAgent: "HulyTest/1.0",
+        });
+        await expect.poll(() => tab.title(), pollTimeout).toBe("390x844 dark HulyTest/1.0");
+
+        // The overrides stay active across navigations.
+        await tab.reload(true);
+        await expect.poll(() => tab.title(), pollTimeout).toBe("390x844 dark HulyTest/1.0");
+
+        await tab.clearEmulation();
+        await expect.poll(() => tab.title(), pollTimeout).toMatch(/^800x600 light /);
+    });
+
+    test('zoom is remembered per origin', async () => {
+        const [first, second] = await Promise.all([serveTestPages(8091), serveTestPages(8092)]);
+        try {
+            const tab = await browser.openTab({ url: "http://localhost:8091/title.html", wait_until_loaded: true });
+            expect((await tab.zoom()).level).toBe(0);
+
+            await tab.zoomIn();
+            const zoomed = await tab.zoom();
+            expect(zoomed.level).toBeGreaterThan(0);
+            expect(zoomed.factor).toBeCloseTo(Math.pow(1.2, zoomed.level));
+
+            await tab.navigate("http://localhost:8092/title.html", true);
+            await expect.poll(async () => (await tab.zoom()).level, pollTimeout).toBe(0);
+
+            await tab.navigate("http://localhost:8091/resize.html", true);
+            await expect.poll(async () => (await tab.zoom()).level, pollTimeout).toBe(zoomed.level);
+
+            // Other tabs of the origin use the remembered level too.
+            const other = await browser.openTab({ url: "http://localhost:8091/title.html", wait_until_loaded: true });
+            await expect.poll(async () => (await other.zoom()).level, pollTimeout).toBe(zoomed.level);
+
+            await tab.resetZoom();
+            expect((await tab.zoom()).level).toBe(0);
+
+            other.close();
+            tab.close();
+        } finally {
+            first.close();
+            second.close();
+        }
+    });
+
+    test('print to pdf', async () => {
+        const tab = await browser.openTab({ url: getPageUrl("title.html"), wait_until_loaded: true });
+
+        const pdf = Buffer.from(await tab.printToPdf({ landscape: true, pageRanges: "1" }), 'base64');
+        expect(pdf.subarray(0, 5).toString()).toBe("%PDF-");
+
+        // Only the file name is used and it always gets the .pdf extension.
+        const path = await tab.savePdf("../../huly-cef-test.txt");
+        expect(basename(dirname(path))).toBe("downloads");
+        expect(basename(path)).toMatch(/^huly-cef-test( \(\d+\))?\.pdf$/);
+        expect(readFileSync(path).subarray(0, 5).toString()).toBe("%PDF-");
+        rmSync(path);
+    });
+
     test('subframes', async () => {
         const tab = await browser.openTab({ url: getPageUrl("frames.html"), wait_until_loaded: true });
         expect(await tab.title()).toBe("Frames");